@@ -1,17 +1,15 @@
 fn main() {
     let mut prost_build = prost_build::Config::new();
     prost_build
-        .type_attribute(
-            ".types.MainMessage.body",
-            "use serde::{Serialize, Deserialize}; #[derive(Serialize, Deserialize)]",
-        )
         .type_attribute(".types.InvalidRequest", "#[derive(Serialize, Deserialize)]")
         .type_attribute(".types.Move", "#[derive(Serialize, Deserialize)]")
         .type_attribute(".types.PlaceTile", "#[derive(Serialize, Deserialize)]")
         .type_attribute(".types.GameState", "#[derive(Serialize, Deserialize)]")
         .type_attribute(".types.GameStatus", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.GameStats", "#[derive(Serialize, Deserialize)]")
         .type_attribute(".types.Player", "#[derive(Serialize, Deserialize)]")
         .type_attribute(".types.Ability", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.Presence", "#[derive(Serialize, Deserialize)]")
         .type_attribute(".types.Heister", "#[derive(Serialize, Deserialize)]")
         .type_attribute(".types.WallType", "#[derive(Serialize, Deserialize)]")
         .type_attribute(".types.SquareType", "#[derive(Serialize, Deserialize)]")
@@ -26,6 +24,39 @@ fn main() {
             "#[derive(Serialize, Deserialize)]",
         )
         .type_attribute(".types.StartGame", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.KickPlayer", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.TransferHost", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.AddBot", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.RemoveBot", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.LeaveGame", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.RenamePlayer", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.ChatMessage", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.QueryMoves", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.PossibleMoves", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.PlanPath", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.PlanStep", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.PathPlan", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.RequestHint", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.Command", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.Hello", "#[derive(Serialize, Deserialize)]")
+        .type_attribute(".types.ServerHello", "#[derive(Serialize, Deserialize)]")
+        // These also apply to the nested oneofs, hence the full paths.
+        .type_attribute(
+            ".types.MainMessage",
+            "#[derive(serde::Serialize, serde::Deserialize)]",
+        )
+        .type_attribute(
+            ".types.Nudge",
+            "#[derive(serde::Serialize, serde::Deserialize)]",
+        )
+        .type_attribute(
+            ".types.Ping",
+            "#[derive(serde::Serialize, serde::Deserialize)]",
+        )
+        .type_attribute(
+            ".types.Hint",
+            "#[derive(serde::Serialize, serde::Deserialize)]",
+        )
         .compile_protos(&["src/types.proto"], &["src/"])
         .unwrap();
 }
@@ -0,0 +1,49 @@
+// Runs the solver over a range of deck seeds, so unwinnable ones can be thrown away.
+//
+// Usage: solve_seeds [first_seed] [num_seeds] [max_states]
+
+use std::env;
+use std::process;
+
+use team_heist_tactics::solver::{new_seeded_game, solve, Solvability, DEFAULT_MAX_STATES};
+
+fn parse_arg<T: std::str::FromStr>(args: &[String], idx: usize, default: T) -> T {
+    match args.get(idx) {
+        Some(arg) => match arg.parse() {
+            Ok(value) => value,
+            Err(_) => {
+                eprintln!("Invalid argument: {}", arg);
+                eprintln!("Usage: solve_seeds [first_seed] [num_seeds] [max_states]");
+                process::exit(1);
+            }
+        },
+        None => default,
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    let args: Vec<String> = env::args().collect();
+    let first_seed: u64 = parse_arg(&args, 1, 0);
+    let num_seeds: u64 = parse_arg(&args, 2, 10);
+    let max_states: usize = parse_arg(&args, 3, DEFAULT_MAX_STATES);
+
+    let mut num_solvable = 0;
+    for seed in first_seed..first_seed + num_seeds {
+        let report = solve(&new_seeded_game(seed), max_states);
+        let result = match report.solvability {
+            Solvability::Solvable { min_moves } => {
+                num_solvable += 1;
+                format!("solvable in {} moves", min_moves)
+            }
+            Solvability::Unsolvable => "UNSOLVABLE".to_string(),
+            Solvability::Unknown => "unknown (hit state limit)".to_string(),
+        };
+        println!(
+            "seed {}: {} ({} states explored, {} timer squares)",
+            seed, result, report.states_explored, report.timer_squares
+        );
+    }
+    println!("{}/{} seeds solvable", num_solvable, num_seeds);
+}
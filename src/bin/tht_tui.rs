@@ -0,0 +1,330 @@
+// A terminal client, for playing and debugging over SSH.
+//
+// Usage: tht-tui <name> <handle> [server_url]
+//
+// If the game has a password, put it in THT_GAME_PASSWORD.
+//
+// Input is read a line at a time. The keys are the same as in the browser:
+// 1-4 select a heister, w/a/s/d move it, e takes the escalator and t uses the
+// first teleporter. r reveals a tile from the door the heister is at. Anything
+// else is sent as a text command, e.g. "green east 3" or "purple teleport 2".
+// Lines starting with / are for the client itself, see HELP below.
+
+use std::collections::HashMap;
+use std::env;
+use std::io::Write;
+use std::process;
+use std::thread;
+
+use anyhow::{anyhow, Result};
+use awc::ws::{Frame, Message};
+use awc::Client;
+use futures::channel::mpsc;
+use futures::future::{self, Either};
+use futures::{SinkExt, StreamExt};
+
+use team_heist_tactics::game_state::GameState;
+use team_heist_tactics::serializer::{InternalMessage, PROTOCOL_VERSION};
+use team_heist_tactics::types::main_message::Body;
+use team_heist_tactics::types::{
+    get_heister_letter, ChatMessage, Command, HeisterColor, Hint, Internal, InvalidRequest,
+    MainMessage, Presence, RequestHint, StartGame,
+};
+use team_heist_tactics::utils::{encode_query_value, get_current_time_secs};
+
+const DEFAULT_SERVER_URL: &str = "http://127.0.0.1:19996";
+const CHAT_LINES: usize = 5;
+const HELP: &str = "1-4 select, w/a/s/d move, e escalator, t teleport, r reveal, \
+                    /say <text>, /start, /hint, /quit";
+
+fn color_name(heister_color: HeisterColor) -> String {
+    format!("{:?}", heister_color).to_lowercase()
+}
+
+fn format_time_left(game_state: &GameState) -> String {
+    if game_state.timer_runs_out == 0 {
+        return "-".to_string();
+    }
+    let secs = game_state
+        .timer_runs_out
+        .saturating_sub(get_current_time_secs());
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+struct Tui {
+    name: String,
+    game_state: Option<GameState>,
+    chat: Vec<String>,
+    selected: HeisterColor,
+    status: String,
+}
+
+impl Tui {
+    fn new(name: String) -> Self {
+        Tui {
+            name,
+            game_state: None,
+            chat: Vec::new(),
+            selected: HeisterColor::Yellow,
+            status: HELP.to_string(),
+        }
+    }
+
+    fn push_chat(&mut self, line: String) {
+        self.chat.push(line);
+        if self.chat.len() > CHAT_LINES {
+            self.chat.remove(0);
+        }
+    }
+
+    fn handle_server_message(&mut self, main_message: MainMessage) {
+        match main_message.body {
+            Some(Body::GameState(gs)) => self.game_state = Some(GameState::from_proto(gs)),
+            Some(Body::InvalidRequest(ir)) => {
+                self.status = format!("Invalid: {}", InvalidRequest::from_proto(ir).reason)
+            }
+            Some(Body::ChatMessage(cm)) => {
+                let chat = ChatMessage::from_proto(cm);
+                self.push_chat(format!("{}: {}", chat.sender.0, chat.text));
+            }
+            Some(Body::Hint(h)) => self.status = format!("Hint: {}", Hint::from_proto(h).text),
+            _ => {}
+        }
+    }
+
+    /// Turns a line of input into a message for the server, if there is one.
+    /// Returns Err if the client should quit.
+    fn handle_input(&mut self, line: &str) -> Result<Option<MainMessage>> {
+        let line = line.trim();
+        let selected = color_name(self.selected);
+        let command = match line {
+            "" => return Ok(None),
+            "/quit" => return Err(anyhow!("Bye")),
+            "/start" => return Ok(Some(body_message(Body::StartGame(StartGame {}.to_proto())))),
+            "/hint" => {
+                return Ok(Some(body_message(Body::RequestHint(
+                    RequestHint {}.to_proto(),
+                ))))
+            }
+            "1" | "2" | "3" | "4" => {
+                self.selected = match line {
+                    "1" => HeisterColor::Yellow,
+                    "2" => HeisterColor::Purple,
+                    "3" => HeisterColor::Green,
+                    _ => HeisterColor::Orange,
+                };
+                self.status = format!("Selected {}", color_name(self.selected));
+                return Ok(None);
+            }
+            "w" => format!("{} north", selected),
+            "d" => format!("{} east", selected),
+            "s" => format!("{} south", selected),
+            "a" => format!("{} west", selected),
+            "e" => format!("{} escalator", selected),
+            "t" => format!("{} teleport 1", selected),
+            "r" => format!("reveal {}", selected),
+            _ => {
+                if let Some(text) = line.strip_prefix("/say ") {
+                    return Ok(Some(body_message(Body::Chat(text.to_string()))));
+                }
+                if line.starts_with('/') {
+                    self.status = HELP.to_string();
+                    return Ok(None);
+                }
+                line.to_string()
+            }
+        };
+        self.status = format!("> {}", command);
+        let command = Command { text: command };
+        Ok(Some(body_message(Body::Command(command.to_proto()))))
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let game_state = match &self.game_state {
+            Some(game_state) => game_state,
+            None => return format!("Connecting...\n{}\n", self.status),
+        };
+        out += &format!(
+            "Game {}  {:?}  Time left: {}  Tiles left: {}  Items taken: {}\n\n",
+            game_state.game_name.0,
+            game_state.game_status,
+            format_time_left(game_state),
+            game_state.remaining_tiles,
+            game_state.all_items_taken,
+        );
+        out += &game_state.render_ascii();
+        out += "\n";
+
+        let mut heisters: HashMap<HeisterColor, String> = HashMap::new();
+        for h in &game_state.heisters {
+            let location = match h.has_escaped {
+                true => "escaped".to_string(),
+                false => format!("({}, {})", h.map_position.x, h.map_position.y),
+            };
+            heisters.insert(h.heister_color, location);
+        }
+        for (i, heister_color) in [
+            HeisterColor::Yellow,
+            HeisterColor::Purple,
+            HeisterColor::Green,
+            HeisterColor::Orange,
+        ]
+        .iter()
+        .enumerate()
+        {
+            let marker = match *heister_color == self.selected {
+                true => "*",
+                false => " ",
+            };
+            out += &format!(
+                "{}{} {} {:<7} {}\n",
+                marker,
+                i + 1,
+                get_heister_letter(*heister_color),
+                color_name(*heister_color),
+                heisters.get(heister_color).cloned().unwrap_or_default(),
+            );
+        }
+        out += "\nPlayers:\n";
+        for player in &game_state.players {
+            let mut tags = Vec::new();
+            if player.name == game_state.host.0 {
+                tags.push("host");
+            }
+            if player.is_bot {
+                tags.push("bot");
+            }
+            if player.name == self.name {
+                tags.push("you");
+            }
+            match player.presence {
+                Presence::Away => tags.push("away"),
+                Presence::Offline => tags.push("offline"),
+                Presence::Online => (),
+            }
+            let tags = match tags.is_empty() {
+                true => "".to_string(),
+                false => format!(" ({})", tags.join(", ")),
+            };
+            out += &format!("  {}{}: {:?}\n", player.name, tags, player.abilities);
+        }
+        out += "\nChat:\n";
+        for line in &self.chat {
+            out += &format!("  {}\n", line);
+        }
+        out += &format!("\n{}\n> ", self.status);
+        out
+    }
+
+    fn draw(&self) {
+        // Clear the screen and move the cursor to the top left.
+        print!("\x1B[2J\x1B[H{}", self.render());
+        std::io::stdout().flush().unwrap();
+    }
+}
+
+fn body_message(body: Body) -> MainMessage {
+    MainMessage { body: Some(body) }
+}
+
+#[actix_rt::main]
+async fn main() -> Result<()> {
+    env_logger::init();
+
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 3 {
+        eprintln!("Usage: tht-tui <name> <handle> [server_url]");
+        process::exit(1);
+    }
+    let name = args[1].clone();
+    let handle = args[2].clone();
+    let server_url = args
+        .get(3)
+        .cloned()
+        .unwrap_or_else(|| DEFAULT_SERVER_URL.to_string());
+    let mut url = format!(
+        "{}/play_game?name={}&handle={}&protocol_version={}",
+        server_url.trim_end_matches('/'),
+        encode_query_value(&name),
+        encode_query_value(&handle),
+        PROTOCOL_VERSION
+    );
+    if let Ok(password) = env::var("THT_GAME_PASSWORD") {
+        url.push_str(&format!("&password={}", encode_query_value(&password)));
+    }
+
+    let (_response, mut framed) = Client::new()
+        .ws(url.as_str())
+        .connect()
+        .await
+        .map_err(|e| anyhow!("Failed to connect to {}: {}", server_url, e))?;
+
+    // Stdin blocks, so read it on its own thread.
+    let (tx, mut rx) = mpsc::unbounded::<String>();
+    thread::spawn(move || loop {
+        let mut line = String::new();
+        match std::io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {
+                if tx.unbounded_send(line).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    let mut tui = Tui::new(name);
+    tui.draw();
+    loop {
+        let event = match future::select(framed.next(), rx.next()).await {
+            Either::Left((frame, _)) => Either::Left(frame),
+            Either::Right((line, _)) => Either::Right(line),
+        };
+        match event {
+            Either::Left(Some(Ok(Frame::Binary(bytes)))) => {
+                match InternalMessage::from_bytes(&bytes) {
+                    Ok(internal_message) => {
+                        tui.handle_server_message(internal_message.main_message)
+                    }
+                    Err(e) => tui.status = format!("Failed to decode message: {}", e),
+                }
+            }
+            Either::Left(Some(Ok(Frame::Ping(bytes)))) => {
+                framed
+                    .send(Message::Pong(bytes))
+                    .await
+                    .map_err(|e| anyhow!("Failed to send pong: {}", e))?;
+                continue;
+            }
+            Either::Left(Some(Ok(Frame::Close(reason)))) => {
+                println!("\nDisconnected: {:?}", reason);
+                return Ok(());
+            }
+            Either::Left(Some(Ok(_))) => continue,
+            Either::Left(Some(Err(e))) => return Err(anyhow!("Websocket error: {}", e)),
+            Either::Left(None) => {
+                println!("\nDisconnected");
+                return Ok(());
+            }
+            Either::Right(Some(line)) => match tui.handle_input(&line) {
+                Ok(Some(main_message)) => {
+                    let internal_message = InternalMessage { main_message };
+                    framed
+                        .send(Message::Binary(internal_message.to_bytes().into()))
+                        .await
+                        .map_err(|e| anyhow!("Failed to send message: {}", e))?;
+                }
+                Ok(None) => {}
+                Err(_) => {
+                    // We're leaving anyway, so it doesn't matter if this fails.
+                    let _ = framed.send(Message::Close(None)).await;
+                    return Ok(());
+                }
+            },
+            // Stdin was closed.
+            Either::Right(None) => return Ok(()),
+        }
+        tui.draw();
+    }
+}
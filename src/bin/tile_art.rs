@@ -0,0 +1,50 @@
+// Converts ASCII tile art into the JSON stored in data/tiles, so new tiles
+// don't have to be transcribed by hand. The art is the output of Tile::pp,
+// followed by a line listing the colors of the teleport pads, items and
+// escapes in reading order, e.g. "colors: purple yellow".
+//
+// Usage: tile_art <art.txt> [output.json]
+//
+// The tile is named after the art file, and written to data/tiles/<name>.json
+// unless another output path is given.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use team_heist_tactics::load_map::tile_art_to_json;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        eprintln!("Usage: tile_art <art.txt> [output.json]");
+        process::exit(1);
+    }
+    let input = Path::new(&args[1]);
+    let name = match input.file_stem() {
+        Some(stem) => stem.to_string_lossy().to_string(),
+        None => {
+            eprintln!("Invalid input path: {}", args[1]);
+            process::exit(1);
+        }
+    };
+    let output = match args.get(2) {
+        Some(output) => output.clone(),
+        None => format!("data/tiles/{}.json", name),
+    };
+
+    let art = fs::read_to_string(input).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", args[1], e);
+        process::exit(1);
+    });
+    let json = tile_art_to_json(&art, &name).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", args[1], e);
+        process::exit(1);
+    });
+    fs::write(&output, json).unwrap_or_else(|e| {
+        eprintln!("Failed to write {}: {}", output, e);
+        process::exit(1);
+    });
+    println!("Wrote tile {} to {}", name, output);
+}
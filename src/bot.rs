@@ -0,0 +1,97 @@
+// Players that are played by the server rather than a person.
+
+use crate::game_state::GameState;
+use crate::planner::plan_to_nearest_goal;
+use crate::types::main_message::Body;
+use crate::types::{Ability, GameStatus, Internal, MainMessage, Move, PlaceTile, PlayerName};
+
+use std::fmt::Debug;
+
+/// Something that can fill a player slot. It is shown every state push, the
+/// same as a websocket client would be, and is periodically asked what it
+/// wants to do next.
+pub trait PlayerAgent: Debug + Send {
+    fn name(&self) -> &PlayerName;
+
+    fn observe(&mut self, game_state: &GameState);
+
+    /// The message to send next, if the agent wants to do anything.
+    fn next_message(&mut self) -> Option<MainMessage>;
+}
+
+/// A bot that only ever uses its own abilities. It reveals tiles whenever it
+/// can, and otherwise helps move heisters towards their items, then the exits,
+/// exploring through doors of the heister's color if the item isn't found yet.
+#[derive(Debug)]
+pub struct SimpleBot {
+    name: PlayerName,
+    teleport_only_from_portal: bool,
+    game_state: Option<GameState>,
+}
+
+impl SimpleBot {
+    pub fn new(name: PlayerName, teleport_only_from_portal: bool) -> Self {
+        SimpleBot {
+            name,
+            teleport_only_from_portal,
+            game_state: None,
+        }
+    }
+
+    fn abilities(&self, game_state: &GameState) -> Vec<Ability> {
+        match game_state.players.iter().find(|p| p.name == self.name.0) {
+            Some(player) => player.abilities.clone(),
+            None => vec![],
+        }
+    }
+}
+
+impl PlayerAgent for SimpleBot {
+    fn name(&self) -> &PlayerName {
+        &self.name
+    }
+
+    fn observe(&mut self, game_state: &GameState) {
+        self.game_state = Some(game_state.clone());
+    }
+
+    fn next_message(&mut self) -> Option<MainMessage> {
+        let game_state = self.game_state.as_ref()?;
+        match game_state.game_status {
+            GameStatus::PreFirstMove | GameStatus::Ongoing => (),
+            _ => return None,
+        }
+        let abilities = self.abilities(game_state);
+
+        if abilities.contains(&Ability::RevealTiles) && game_state.remaining_tiles > 0 {
+            if let Some(tile_entrance) = game_state.possible_placements.first() {
+                let pt = PlaceTile {
+                    tile_entrance: *tile_entrance,
+                };
+                return Some(MainMessage {
+                    body: Some(Body::PlaceTile(pt.to_proto())),
+                });
+            }
+        }
+
+        for heister in game_state.heisters.iter().filter(|h| !h.has_escaped) {
+            let first_step =
+                match plan_to_nearest_goal(game_state, heister, self.teleport_only_from_portal) {
+                    Some(steps) => steps[0].clone(),
+                    None => continue,
+                };
+            // Leave the steps we can't make to the other players.
+            if !abilities.contains(&first_step.ability) {
+                continue;
+            }
+            let m = Move {
+                heister_color: heister.heister_color,
+                position: first_step.position,
+            };
+            return Some(MainMessage {
+                body: Some(Body::Move(m.to_proto())),
+            });
+        }
+        None
+    }
+}
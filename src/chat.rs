@@ -0,0 +1,124 @@
+// Keeps track of the chat for a game, and limits how quickly players can
+// send chats and other signals to each other.
+
+use crate::types::{ChatMessage, PlayerName};
+use crate::utils::get_current_time_secs;
+
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, VecDeque};
+
+// How many messages we keep around to send to players who (re)join.
+pub const CHAT_HISTORY_LENGTH: usize = 100;
+pub const MAX_CHAT_LENGTH: usize = 500;
+// Each player may send at most this many messages in this many seconds.
+pub const CHAT_RATE_LIMIT_MESSAGES: usize = 5;
+pub const CHAT_RATE_LIMIT_WINDOW_SECS: u64 = 5;
+pub const NUDGE_RATE_LIMIT_MESSAGES: usize = 3;
+pub const NUDGE_RATE_LIMIT_WINDOW_SECS: u64 = 10;
+pub const PING_RATE_LIMIT_MESSAGES: usize = 5;
+pub const PING_RATE_LIMIT_WINDOW_SECS: u64 = 10;
+
+// Chats that don't count as speaking, so they are allowed even in silent play.
+pub const NON_VERBAL_SIGNALS: [&'static str; 5] = ["!", "?", "👀", "👍", "👎"];
+
+pub fn is_non_verbal_signal(text: &str) -> bool {
+    NON_VERBAL_SIGNALS.contains(&text.trim())
+}
+
+/// Limits each player to a number of sends within a sliding window.
+#[derive(Debug)]
+pub struct PlayerRateLimiter {
+    max_sends: usize,
+    window_secs: u64,
+    // When each player recently sent something.
+    recent_sends: HashMap<PlayerName, VecDeque<u64>>,
+}
+
+impl PlayerRateLimiter {
+    pub fn new(max_sends: usize, window_secs: u64) -> Self {
+        PlayerRateLimiter {
+            max_sends,
+            window_secs,
+            recent_sends: HashMap::new(),
+        }
+    }
+
+    /// Record a send at time `now` if the player is within their limit.
+    /// Returns whether the send is allowed.
+    pub fn try_send(&mut self, player_name: &PlayerName, now: u64) -> bool {
+        let window_secs = self.window_secs;
+        let recent_sends = self
+            .recent_sends
+            .entry(player_name.clone())
+            .or_insert_with(VecDeque::new);
+        while let Some(sent) = recent_sends.front() {
+            if sent + window_secs <= now {
+                recent_sends.pop_front();
+            } else {
+                break;
+            }
+        }
+        if recent_sends.len() >= self.max_sends {
+            return false;
+        }
+        recent_sends.push_back(now);
+        true
+    }
+}
+
+#[derive(Debug)]
+pub struct ChatLog {
+    messages: VecDeque<ChatMessage>,
+    next_id: u64,
+    rate_limiter: PlayerRateLimiter,
+}
+
+impl ChatLog {
+    pub fn new() -> Self {
+        ChatLog {
+            messages: VecDeque::new(),
+            next_id: 0,
+            rate_limiter: PlayerRateLimiter::new(
+                CHAT_RATE_LIMIT_MESSAGES,
+                CHAT_RATE_LIMIT_WINDOW_SECS,
+            ),
+        }
+    }
+
+    /// Validate a chat from a player and add it to the history.
+    /// Returns the message as it should be sent out to everyone.
+    pub fn add_message(&mut self, sender: &PlayerName, text: String) -> Result<ChatMessage> {
+        let now = get_current_time_secs();
+        let text = text.trim().to_string();
+        if text.is_empty() {
+            return Err(anyhow!("Chat message cannot be empty"));
+        }
+        if text.chars().count() > MAX_CHAT_LENGTH {
+            return Err(anyhow!(
+                "Chat message cannot be longer than {} characters",
+                MAX_CHAT_LENGTH
+            ));
+        }
+        if !self.rate_limiter.try_send(sender, now) {
+            return Err(anyhow!("You are sending chat messages too quickly"));
+        }
+
+        let chat_message = ChatMessage {
+            sender: sender.clone(),
+            timestamp: now,
+            text,
+            id: self.next_id,
+        };
+        self.next_id += 1;
+        self.messages.push_back(chat_message.clone());
+        if self.messages.len() > CHAT_HISTORY_LENGTH {
+            self.messages.pop_front();
+        }
+        Ok(chat_message)
+    }
+
+    /// The most recent messages, oldest first.
+    pub fn get_history(&self) -> Vec<ChatMessage> {
+        self.messages.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,74 @@
+// Why the server closed a websocket. Joining can't fail with an HTTP error,
+// because browsers don't let the page see it, so instead the upgrade succeeds
+// and the connection is closed straight away with one of these. The code and
+// message go in the close frame, so clients can tell the player what happened
+// and know not to reconnect.
+
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CloseReason {
+    GameNotFound(String),
+    GameFull,
+    GameAlreadyStarted,
+    BadPassword,
+    InvalidName(String),
+    IncompatibleProtocol(String),
+    Kicked,
+    LeftGame,
+    GameReaped,
+    RateLimited,
+    TooManyPasswordAttempts,
+    JoinedElsewhere,
+}
+
+impl CloseReason {
+    /// Codes from 4000 to 4999 are for applications to define.
+    pub fn code(&self) -> u16 {
+        match self {
+            CloseReason::GameNotFound(_) => 4000,
+            CloseReason::GameFull => 4001,
+            CloseReason::GameAlreadyStarted => 4002,
+            CloseReason::BadPassword => 4003,
+            CloseReason::InvalidName(_) => 4004,
+            CloseReason::IncompatibleProtocol(_) => 4005,
+            CloseReason::Kicked => 4006,
+            CloseReason::LeftGame => 4007,
+            CloseReason::GameReaped => 4008,
+            CloseReason::RateLimited => 4009,
+            CloseReason::TooManyPasswordAttempts => 4010,
+            CloseReason::JoinedElsewhere => 4011,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            CloseReason::GameNotFound(handle) => {
+                format!("Game with handle \"{}\" does not exist", handle)
+            }
+            CloseReason::GameFull => "The game is full".to_string(),
+            CloseReason::GameAlreadyStarted => "The game has already started".to_string(),
+            CloseReason::BadPassword => "The password for this game is wrong".to_string(),
+            CloseReason::InvalidName(reason) => reason.clone(),
+            CloseReason::IncompatibleProtocol(reason) => reason.clone(),
+            CloseReason::Kicked => "You were kicked from the game by the host".to_string(),
+            CloseReason::LeftGame => "You left the game".to_string(),
+            CloseReason::GameReaped => "The game ended because it was too old".to_string(),
+            CloseReason::RateLimited => "You sent too many messages too quickly".to_string(),
+            CloseReason::TooManyPasswordAttempts => {
+                "Too many wrong passwords, try again later".to_string()
+            }
+            CloseReason::JoinedElsewhere => {
+                "You joined this game again from somewhere else".to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.description(), self.code())
+    }
+}
+
+impl std::error::Error for CloseReason {}
@@ -0,0 +1,171 @@
+// Turns typed commands into moves, for playing without the board.
+//
+// The commands are:
+//   <color> <direction> [squares]   e.g. "yellow north", "green east 3"
+//   <color> escalator               e.g. "orange escalator"
+//   <color> teleport <number>       e.g. "purple teleport 2"
+//   reveal <color>                  e.g. "reveal yellow"
+//
+// Teleports are numbered from 1 in the order the teleporters were revealed.
+
+use crate::game_state::GameState;
+use crate::types::main_message::Body;
+use crate::types::{HeisterColor, Internal, MapPosition, Move, MoveDirection, PlaceTile};
+
+use anyhow::{anyhow, Result};
+
+fn parse_heister_color(word: &str) -> Result<HeisterColor> {
+    match word {
+        "yellow" => Ok(HeisterColor::Yellow),
+        "purple" => Ok(HeisterColor::Purple),
+        "green" => Ok(HeisterColor::Green),
+        "orange" => Ok(HeisterColor::Orange),
+        _ => Err(anyhow!(
+            "Unknown heister \"{}\", expected yellow, purple, green or orange",
+            word
+        )),
+    }
+}
+
+fn parse_direction(word: &str) -> Option<MoveDirection> {
+    match word {
+        "north" | "n" => Some(MoveDirection::North),
+        "east" | "e" => Some(MoveDirection::East),
+        "south" | "s" => Some(MoveDirection::South),
+        "west" | "w" => Some(MoveDirection::West),
+        _ => None,
+    }
+}
+
+fn parse_number(word: Option<&&str>, what: &str) -> Result<usize> {
+    let word = match word {
+        Some(word) => word,
+        None => return Err(anyhow!("Expected a number of {}", what)),
+    };
+    match word.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(anyhow!("\"{}\" is not a valid number of {}", word, what)),
+    }
+}
+
+fn heister_position(game_state: &GameState, heister_color: HeisterColor) -> Result<MapPosition> {
+    match game_state.get_heister_from_vec(heister_color) {
+        Some(h) if !h.has_escaped => Ok(h.map_position),
+        _ => Err(anyhow!(
+            "The {:?} heister has already escaped",
+            heister_color
+        )),
+    }
+}
+
+fn make_move(heister_color: HeisterColor, position: MapPosition) -> Body {
+    let m = Move {
+        heister_color,
+        position,
+    };
+    Body::Move(m.to_proto())
+}
+
+/// Parse a text command into the Move or PlaceTile it describes, given the
+/// current state of the board. This doesn't check the move is allowed, the
+/// game does that when the move is made.
+pub fn parse_command(text: &str, game_state: &GameState) -> Result<Body> {
+    let text = text.trim().to_lowercase();
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let (first, rest) = match words.split_first() {
+        Some(split) => split,
+        None => return Err(anyhow!("Empty command")),
+    };
+
+    if first == &"reveal" {
+        if rest.len() != 1 {
+            return Err(anyhow!("Expected \"reveal <color>\""));
+        }
+        let heister_color = parse_heister_color(rest[0])?;
+        let heister_pos = heister_position(game_state, heister_color)?;
+        let grid = game_state.get_absolute_grid();
+        return match game_state
+            .heister_to_tile_entrance_positions(&grid)
+            .get(&heister_pos)
+        {
+            Some(tile_entrance) => {
+                let pt = PlaceTile {
+                    tile_entrance: *tile_entrance,
+                };
+                Ok(Body::PlaceTile(pt.to_proto()))
+            }
+            None => Err(anyhow!(
+                "The {:?} heister is not at a door of its color",
+                heister_color
+            )),
+        };
+    }
+
+    let heister_color = parse_heister_color(first)?;
+    let heister_pos = heister_position(game_state, heister_color)?;
+    let action = match rest.first() {
+        Some(action) => *action,
+        None => return Err(anyhow!("Expected a direction, escalator or teleport")),
+    };
+
+    if let Some(direction) = parse_direction(action) {
+        let squares = match rest.len() {
+            1 => 1,
+            2 => parse_number(rest.get(1), "squares")?,
+            _ => return Err(anyhow!("Too many words in command")),
+        };
+        // No move can be longer than the board, and this keeps huge numbers
+        // from tying up the game working out where they'd end up.
+        let board_size = game_state.get_absolute_grid().len();
+        if squares > board_size {
+            return Err(anyhow!(
+                "Can't move {} squares, the board only has {}",
+                squares,
+                board_size
+            ));
+        }
+        let mut position = heister_pos;
+        for _ in 0..squares {
+            position = position.move_in_direction(&direction);
+        }
+        return Ok(make_move(heister_color, position));
+    }
+
+    match action {
+        "escalator" => {
+            if rest.len() != 1 {
+                return Err(anyhow!("Too many words in command"));
+            }
+            let grid = game_state.get_absolute_grid();
+            match game_state.get_escalator_destination(&grid, &heister_pos) {
+                Some(dest) => Ok(make_move(heister_color, dest)),
+                None => Err(anyhow!(
+                    "The {:?} heister is not on an escalator",
+                    heister_color
+                )),
+            }
+        }
+        "teleport" => {
+            if rest.len() != 2 {
+                return Err(anyhow!("Expected \"<color> teleport <number>\""));
+            }
+            let n = parse_number(rest.get(1), "teleporters")?;
+            let teleports = match game_state.possible_teleports.get(&heister_color) {
+                Some(teleports) => teleports,
+                None => return Err(anyhow!("The {:?} heister can't teleport", heister_color)),
+            };
+            match teleports.get(n - 1) {
+                Some(dest) => Ok(make_move(heister_color, *dest)),
+                None => Err(anyhow!(
+                    "There are only {} teleporters for the {:?} heister",
+                    teleports.len(),
+                    heister_color
+                )),
+            }
+        }
+        _ => Err(anyhow!(
+            "Unknown action \"{}\", expected a direction, escalator or teleport",
+            action
+        )),
+    }
+}
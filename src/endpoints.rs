@@ -1,19 +1,40 @@
+use crate::close_reason::CloseReason;
 use crate::errors::MyError;
 use crate::game::{GameHandle, GameOptions, MoveValidity};
-use crate::manager::{GameManagerWrapper, GameWrapper, JoinOptions};
-use crate::serializer::InternalMessage;
-use crate::types::PlayerName;
-use crate::utils::empty_string_as_none;
+use crate::manager::{CreateOptions, GameManagerWrapper, GameSummary, GameWrapper, JoinOptions};
+use crate::password::{check_password, hash_password};
+use crate::rate_limit::{
+    message_type, ConnectionRateLimiter, PasswordAttemptLimiter, RateLimitConfig, RateLimitDecision,
+};
+use crate::serializer::{
+    check_protocol_version, InternalMessage, WireFormat, MAX_FRAME_BYTES, SUBPROTOCOLS,
+};
+use crate::svg::render_svg;
+use crate::types::main_message::Body;
+use crate::types::{Hello, Internal, MainMessage, PlayerName, Presence};
+use crate::utils::{empty_string_as_none, encode_query_value};
 
-use log::{debug, info, trace, warn};
+use log::{debug, error, info, warn};
 use std::fs::File;
 use std::io::{BufReader, Read};
 
-use actix::{Actor, Handler, StreamHandler};
+use actix::{Actor, ActorContext, AsyncContext, Handler, Message, StreamHandler};
 use actix_web::{http::header, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
 use actix_web_actors::ws;
 use serde::Deserialize;
+use std::net::{IpAddr, Ipv4Addr};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+// How often we ping clients, how long they can go without answering before
+// they're shown as away, and how long before we give up on them.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+const AWAY_TIMEOUT: Duration = Duration::from_secs(15);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(60);
+// How long a client has to say which protocol version it speaks.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+// How often lobby websockets check whether the list of games changed.
+const LOBBY_INTERVAL: Duration = Duration::from_secs(2);
 
 pub async fn index() -> impl Responder {
     let file = File::open("templates/index.html");
@@ -47,6 +68,18 @@ pub async fn play() -> impl Responder {
 pub struct CreateGameFormData {
     #[serde(deserialize_with = "empty_string_as_none")]
     game_handle: Option<String>,
+    // The name of the player creating the game, who becomes the host.
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    name: Option<String>,
+    // Checkbox, only present if ticked.
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    enforce_silence: Option<String>,
+    // Checkbox, lists the game in the lobby.
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    public: Option<String>,
+    // Players have to give this to join, if set.
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    password: Option<String>,
 }
 
 pub async fn create_game(
@@ -54,11 +87,34 @@ pub async fn create_game(
     form: web::Form<CreateGameFormData>,
     game_manager_wrapper: web::Data<GameManagerWrapper>,
 ) -> impl Responder {
+    let password_hash = match form.password.clone() {
+        Some(password) => {
+            let hashed = web::block(move || -> Result<String, ()> { Ok(hash_password(&password)) });
+            match hashed.await {
+                Ok(password_hash) => Some(password_hash),
+                Err(e) => {
+                    error!("Failed to hash game password: {:?}", e);
+                    return HttpResponse::InternalServerError().finish();
+                }
+            }
+        }
+        None => None,
+    };
+
     let mut game_manager = game_manager_wrapper.game_manager.write().unwrap();
 
     // Register a new game.
-    let game_options = GameOptions::default();
-    let game_handle = game_manager.new_game(game_options, form.game_handle.clone());
+    let game_options = GameOptions {
+        enforce_silence: form.enforce_silence.is_some(),
+        ..GameOptions::default()
+    };
+    let create_options = CreateOptions {
+        handle: form.game_handle.clone(),
+        host: form.name.clone().map(PlayerName),
+        public: form.public.is_some(),
+        password_hash,
+    };
+    let game_handle = game_manager.new_game(game_options, create_options);
     let game_handle = match game_handle {
         Ok(game_handle) => game_handle,
         Err(e) => return HttpResponse::from_error(MyError::from(e).into()),
@@ -66,18 +122,231 @@ pub async fn create_game(
 
     // Get the handle to the game and return a redirect to play/handle=<that page>.
     // The frontend will use the last part of the URL to build the join_game request.
+    // The host's name goes along too, so they join under the name they're host as.
     // TODO Use proper params builder for this, like url_for.
-    let location = format!("play?handle={}", game_handle.0.to_string());
+    let mut location = format!("play?handle={}", encode_query_value(&game_handle.0));
+    if let Some(name) = &form.name {
+        location.push_str(&format!("&name={}", encode_query_value(name)));
+    }
 
     HttpResponse::SeeOther()
         .header(header::LOCATION, location)
         .finish()
 }
 
+#[derive(Deserialize)]
+pub struct PasswordQuery {
+    // Needed for games with a password.
+    #[serde(default)]
+    password: Option<String>,
+}
+
+/// For the read only endpoints, which need the game's password just like
+/// joining does. The error is the response to send instead.
+async fn get_game_with_password(
+    req: &HttpRequest,
+    handle: GameHandle,
+    password: Option<String>,
+    game_manager_wrapper: &GameManagerWrapper,
+    password_attempt_limiter: &PasswordAttemptLimiter,
+) -> Result<Arc<RwLock<GameWrapper>>, HttpResponse> {
+    let (game_wrapper, password_hash) = {
+        let game_manager = game_manager_wrapper.game_manager.read().unwrap();
+        match game_manager.get_game(&handle) {
+            Ok(game_wrapper) => (game_wrapper, game_manager.get_password_hash(&handle)),
+            Err(e) => return Err(HttpResponse::NotFound().body(e.to_string())),
+        }
+    };
+    let password_check = check_game_password(
+        req,
+        &handle,
+        password_hash,
+        password,
+        password_attempt_limiter,
+    )
+    .await;
+    match password_check {
+        Ok(()) => Ok(game_wrapper),
+        Err(CloseReason::TooManyPasswordAttempts) => Err(HttpResponse::TooManyRequests()
+            .body(CloseReason::TooManyPasswordAttempts.description())),
+        Err(close_reason) => Err(HttpResponse::Forbidden().body(close_reason.description())),
+    }
+}
+
+// A picture of the board as it is right now, e.g. for sharing finished games.
+pub async fn board_svg(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<PasswordQuery>,
+    game_manager_wrapper: web::Data<GameManagerWrapper>,
+    password_attempt_limiter: web::Data<PasswordAttemptLimiter>,
+) -> impl Responder {
+    let game_wrapper = match get_game_with_password(
+        &req,
+        GameHandle(path.into_inner()),
+        query.password.clone(),
+        &game_manager_wrapper,
+        &password_attempt_limiter,
+    )
+    .await
+    {
+        Ok(game_wrapper) => game_wrapper,
+        Err(resp) => return resp,
+    };
+    let svg = render_svg(game_wrapper.read().unwrap().get_game_state());
+    HttpResponse::Ok().content_type("image/svg+xml").body(svg)
+}
+
+// Read only JSON API, for dashboards and scripts. Only public games without a
+// password are listed, so other games can't be found here, and the endpoints
+// for a single game need its password if it has one.
+pub async fn api_games(game_manager_wrapper: web::Data<GameManagerWrapper>) -> impl Responder {
+    let game_manager = game_manager_wrapper.game_manager.read().unwrap();
+    let summaries: Vec<GameSummary> = game_manager
+        .get_public_games()
+        .into_iter()
+        .filter(|s| !s.has_password)
+        .collect();
+    HttpResponse::Ok().json(summaries)
+}
+
+pub async fn api_game(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<PasswordQuery>,
+    game_manager_wrapper: web::Data<GameManagerWrapper>,
+    password_attempt_limiter: web::Data<PasswordAttemptLimiter>,
+) -> impl Responder {
+    let game_wrapper = match get_game_with_password(
+        &req,
+        GameHandle(path.into_inner()),
+        query.password.clone(),
+        &game_manager_wrapper,
+        &password_attempt_limiter,
+    )
+    .await
+    {
+        Ok(game_wrapper) => game_wrapper,
+        Err(resp) => return resp,
+    };
+    let game_state = game_wrapper.read().unwrap().get_game_state().to_proto();
+    HttpResponse::Ok().json(game_state)
+}
+
+pub async fn api_game_players(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<PasswordQuery>,
+    game_manager_wrapper: web::Data<GameManagerWrapper>,
+    password_attempt_limiter: web::Data<PasswordAttemptLimiter>,
+) -> impl Responder {
+    let game_wrapper = match get_game_with_password(
+        &req,
+        GameHandle(path.into_inner()),
+        query.password.clone(),
+        &game_manager_wrapper,
+        &password_attempt_limiter,
+    )
+    .await
+    {
+        Ok(game_wrapper) => game_wrapper,
+        Err(resp) => return resp,
+    };
+    let game_state = game_wrapper.read().unwrap().get_game_state().to_proto();
+    HttpResponse::Ok().json(game_state.players)
+}
+
+// Public games waiting for players. Browsers get a page that keeps the list
+// up to date over /lobby/ws, anything asking for JSON gets the list itself.
+pub async fn lobby(
+    req: HttpRequest,
+    game_manager_wrapper: web::Data<GameManagerWrapper>,
+) -> impl Responder {
+    let wants_json = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|h| h.to_str().ok())
+        .map_or(false, |h| h.contains("application/json"));
+    if wants_json {
+        let game_manager = game_manager_wrapper.game_manager.read().unwrap();
+        return HttpResponse::Ok().json(game_manager.get_lobby_games());
+    }
+    let file = File::open("templates/lobby.html");
+    let file = match file {
+        Ok(body) => body,
+        Err(e) => return HttpResponse::from_error(e.into()),
+    };
+    let mut buf_reader = BufReader::new(file);
+    let mut contents = String::new();
+    buf_reader
+        .read_to_string(&mut contents)
+        .expect("Failed to read lobby.html into buffer");
+    HttpResponse::Ok().body(contents)
+}
+
+pub async fn lobby_ws(
+    req: HttpRequest,
+    stream: web::Payload,
+    game_manager_wrapper: web::Data<GameManagerWrapper>,
+) -> impl Responder {
+    let lobby_ws = LobbyWs {
+        game_manager_wrapper: game_manager_wrapper.clone(),
+        last_sent: None,
+    };
+    match ws::start(lobby_ws, &req, stream) {
+        Ok(resp) => resp,
+        Err(e) => HttpResponse::from_error(e),
+    }
+}
+
 #[derive(Deserialize)]
 pub struct JoinGameQuery {
     name: String,
     handle: String,
+    // Instead of sending a Hello first.
+    #[serde(default)]
+    protocol_version: Option<u32>,
+    // Watch the game if it's full or has started, rather than failing to join.
+    #[serde(default)]
+    spectate: bool,
+    #[serde(default)]
+    password: Option<String>,
+}
+
+fn peer_ip(req: &HttpRequest) -> IpAddr {
+    req.peer_addr()
+        .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |addr| addr.ip())
+}
+
+/// Check the password for a game with the given password hash, if it has
+/// one. Wrong guesses are limited per game and address.
+async fn check_game_password(
+    req: &HttpRequest,
+    handle: &GameHandle,
+    password_hash: Option<String>,
+    password: Option<String>,
+    password_attempt_limiter: &PasswordAttemptLimiter,
+) -> Result<(), CloseReason> {
+    let password_hash = match password_hash {
+        Some(password_hash) => password_hash,
+        None => return Ok(()),
+    };
+    let ip = peer_ip(req);
+    if !password_attempt_limiter.try_attempt(handle, ip, Instant::now()) {
+        return Err(CloseReason::TooManyPasswordAttempts);
+    }
+    let correct = web::block(move || -> Result<bool, ()> {
+        Ok(check_password(Some(&password_hash), password.as_deref()))
+    })
+    .await
+    .unwrap_or(false);
+    match correct {
+        true => {
+            password_attempt_limiter.succeeded(handle, ip);
+            Ok(())
+        }
+        false => Err(CloseReason::BadPassword),
+    }
 }
 
 // TODO Make the input here a struct and use whatever actix offers for this purpose.
@@ -89,44 +358,99 @@ pub async fn play_game(
     info: web::Query<JoinGameQuery>,
     stream: web::Payload,
     game_manager_wrapper: web::Data<GameManagerWrapper>,
+    rate_limit_config: web::Data<RateLimitConfig>,
+    password_attempt_limiter: web::Data<PasswordAttemptLimiter>,
 ) -> impl Responder {
     debug!("Player {} joining game {}", info.name, info.handle);
-    let mut game_manager = game_manager_wrapper.game_manager.write().unwrap();
+    // This is what ws::start_with_addr does, but it also agrees on a subprotocol.
+    let mut resp = match ws::handshake_with_protocols(&req, &SUBPROTOCOLS) {
+        Ok(resp) => resp,
+        Err(e) => return HttpResponse::from_error(e.into()),
+    };
+
+    // A client that gave its version when joining can be turned away before
+    // it becomes a player. Others are checked when they say hello.
+    if let Some(protocol_version) = info.protocol_version {
+        if let Err(e) = check_protocol_version(protocol_version) {
+            info!(
+                "Player {} can't join game {}: {}",
+                info.name, info.handle, e
+            );
+            let rejected_ws = RejectedWs {
+                close_reason: CloseReason::IncompatibleProtocol(e.to_string()),
+            };
+            return resp.streaming(ws::WebsocketContext::create(rejected_ws, stream));
+        }
+    }
+
+    // Checked first, so strangers can't find out anything about the game.
     let handle = GameHandle(info.handle.to_string());
+    let password_hash = game_manager_wrapper
+        .game_manager
+        .read()
+        .unwrap()
+        .get_password_hash(&handle);
+    let password_check = check_game_password(
+        &req,
+        &handle,
+        password_hash.clone(),
+        info.password.clone(),
+        &password_attempt_limiter,
+    )
+    .await;
+
+    let mut game_manager = game_manager_wrapper.game_manager.write().unwrap();
+    // The game could have been reaped and a new one made with the same handle
+    // while the password was being checked.
+    let password_check = match game_manager.get_password_hash(&handle) == password_hash {
+        true => password_check,
+        false => Err(CloseReason::BadPassword),
+    };
     let join_options = JoinOptions {
         name: info.name.to_string(),
-        handle: handle.clone(),
+        handle,
+        spectate: info.spectate,
     };
-    let game_wrapper = game_manager.join_game(join_options);
-    let game_wrapper = match game_wrapper {
+    let joined = password_check.and_then(|_| game_manager.join_game(join_options));
+    let game_wrapper = match joined {
         Ok(game_wrapper) => game_wrapper,
-        Err(e) => return HttpResponse::from_error(MyError::from(e).into()),
+        Err(close_reason) => {
+            info!(
+                "Player {} failed to join game {}: {}",
+                info.name, info.handle, close_reason
+            );
+            let rejected_ws = RejectedWs { close_reason };
+            return resp.streaming(ws::WebsocketContext::create(rejected_ws, stream));
+        }
     };
 
+    let wire_format = WireFormat::from_protocols_header(
+        req.headers()
+            .get(header::SEC_WEBSOCKET_PROTOCOL)
+            .and_then(|h| h.to_str().ok()),
+    );
     let my_ws = MyWs {
         game_wrapper: game_wrapper.clone(),
         player_name: PlayerName(info.name.clone()),
+        wire_format,
+        protocol_version: info.protocol_version,
+        last_heartbeat: Instant::now(),
+        away: false,
+        rate_limiter: ConnectionRateLimiter::new(
+            rate_limit_config.get_ref().clone(),
+            Instant::now(),
+        ),
     };
     debug!(
-        "Created actor for player {} joining game {}",
-        info.name, info.handle
+        "Created actor for player {} joining game {} speaking {:?}",
+        info.name, info.handle, wire_format
     );
 
-    let res = ws::start_with_addr(my_ws, &req, stream);
-    let (addr, resp) = match res {
-        Ok(res) => res,
-        Err(e) => return HttpResponse::from_error(e),
-    };
-    debug!(
-        "Registering actor for player {} joining game {}",
-        info.name, info.handle
-    );
-    game_manager.register_actor(handle, addr);
-    trace!(
-        "Registered actor for player {} joining game {}",
-        info.name,
-        info.handle
-    );
+    // Frames over the limit are refused while being read, rather than after
+    // they've been buffered. The actor registers itself once it has started
+    // and its protocol version is accepted.
+    let codec = ws::Codec::new().max_size(MAX_FRAME_BYTES);
+    let resp = resp.streaming(ws::WebsocketContext::with_codec(my_ws, stream, codec));
 
     debug!(
         "Websocket for player {} in game {} upgraded successfully",
@@ -139,37 +463,252 @@ pub async fn play_game(
 pub struct MyWs {
     game_wrapper: Arc<RwLock<GameWrapper>>,
     player_name: PlayerName,
+    wire_format: WireFormat,
+    // None until the version the client speaks is accepted, either when it
+    // joined or by a Hello. Until then the actor isn't registered, so it gets
+    // no state or chat.
+    protocol_version: Option<u32>,
+    // When we last heard anything from the client.
+    last_heartbeat: Instant,
+    away: bool,
+    rate_limiter: ConnectionRateLimiter,
+}
+
+impl MyWs {
+    fn send(&self, msg: &InternalMessage, ctx: &mut ws::WebsocketContext<Self>) {
+        match self.wire_format {
+            WireFormat::Proto => ctx.binary(msg.to_bytes()),
+            WireFormat::Json => ctx.text(msg.to_json()),
+        }
+    }
+
+    fn close(&self, close_reason: CloseReason, ctx: &mut ws::WebsocketContext<Self>) {
+        info!(
+            "Closing connection for player {}: {}",
+            self.player_name.0, close_reason
+        );
+        ctx.close(Some(to_ws_close_reason(&close_reason)));
+        ctx.stop();
+    }
+
+    /// Tells the client why its message was rejected, or disconnects it if
+    /// it keeps sending messages that are.
+    fn reject(&mut self, decision: RateLimitDecision, ctx: &mut ws::WebsocketContext<Self>) {
+        match decision {
+            RateLimitDecision::Allowed => (),
+            RateLimitDecision::Limited(reason) => {
+                debug!("Rejected message from {}: {}", self.player_name.0, reason);
+                self.send(&InternalMessage::from_invalid_reason(reason), ctx);
+            }
+            RateLimitDecision::Disconnect => self.close(CloseReason::RateLimited, ctx),
+        }
+    }
+
+    fn heartbeat(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        let since_heartbeat = Instant::now().duration_since(self.last_heartbeat);
+        if since_heartbeat > CLIENT_TIMEOUT {
+            info!(
+                "Player {} timed out after {:?}, disconnecting",
+                self.player_name.0, since_heartbeat
+            );
+            // stopped() takes care of marking them offline.
+            ctx.stop();
+            return;
+        }
+        if since_heartbeat > AWAY_TIMEOUT && !self.away {
+            self.away = true;
+            self.update_presence(Presence::Away, ctx);
+        }
+        ctx.ping(b"");
+    }
+
+    fn got_heartbeat(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        self.last_heartbeat = Instant::now();
+        if self.away {
+            self.away = false;
+            self.update_presence(Presence::Online, ctx);
+        }
+    }
+
+    fn update_presence(&self, presence: Presence, ctx: &mut ws::WebsocketContext<Self>) {
+        let mut game_wrapper = self.game_wrapper.write().unwrap();
+        if game_wrapper.set_actor_presence(&self.player_name, &ctx.address(), presence) {
+            if let Err(e) = game_wrapper.push_state() {
+                warn!("Failed to push state after presence changed: {:?}", e);
+            }
+        }
+    }
+
+    fn register(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        debug!("Registering actor for player {}", self.player_name.0);
+        self.game_wrapper
+            .write()
+            .unwrap()
+            .register_actor(self.player_name.clone(), ctx.address());
+    }
+
+    fn accept_protocol_version(
+        &mut self,
+        protocol_version: u32,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        match check_protocol_version(protocol_version) {
+            Ok(_) => {
+                let registered = self.protocol_version.is_some();
+                self.protocol_version = Some(protocol_version);
+                self.send(&InternalMessage::from_server_hello(), ctx);
+                if !registered {
+                    self.register(ctx);
+                }
+            }
+            Err(e) => self.close(CloseReason::IncompatibleProtocol(e.to_string()), ctx),
+        }
+    }
+
+    /// Handles the version handshake. Returns true if the message was part of
+    /// it, or the connection is being closed, so it shouldn't go to the game.
+    fn handle_handshake(
+        &mut self,
+        main_message: &MainMessage,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) -> bool {
+        match (&main_message.body, self.protocol_version) {
+            (Some(Body::Hello(h)), _) => {
+                let hello = Hello::from_proto(h.clone());
+                debug!(
+                    "Player {} says hello from {} speaking protocol version {}",
+                    self.player_name.0, hello.client_name, hello.protocol_version
+                );
+                self.accept_protocol_version(hello.protocol_version, ctx);
+                true
+            }
+            (_, None) => {
+                // Most likely old cached JS, which doesn't know to say hello.
+                let reason = "Expected a Hello message first, try reloading the page";
+                self.close(CloseReason::IncompatibleProtocol(reason.to_string()), ctx);
+                true
+            }
+            (_, Some(_)) => false,
+        }
+    }
 }
 
 impl Actor for MyWs {
     type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| act.heartbeat(ctx));
+        // The client gave its version when joining, which play_game already
+        // checked, so it won't send a Hello.
+        if self.protocol_version.is_some() {
+            self.send(&InternalMessage::from_server_hello(), ctx);
+            self.register(ctx);
+            return;
+        }
+        ctx.run_later(HANDSHAKE_TIMEOUT, |act, ctx| {
+            if act.protocol_version.is_none() {
+                let reason = "Didn't say hello in time, try reloading the page";
+                act.close(CloseReason::IncompatibleProtocol(reason.to_string()), ctx);
+            }
+        });
+    }
+
+    fn stopped(&mut self, ctx: &mut Self::Context) {
+        let mut game_wrapper = self.game_wrapper.write().unwrap();
+        game_wrapper.remove_actor(&self.player_name, &ctx.address());
+        // Let everyone else know, e.g. if the host changed.
+        match game_wrapper.push_state() {
+            Ok(_) => (),
+            Err(e) => warn!("Failed to push state after actor stopped: {:?}", e),
+        }
+    }
 }
 
 // This impl handles messages received from the client.
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for MyWs {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
-        if let Ok(ws::Message::Ping(ping)) = msg {
-            debug!("Ponging: {:?}", ping);
-            ctx.pong(&ping);
-            return;
+        // Anything from the client shows it's still there.
+        if msg.is_ok() {
+            self.got_heartbeat(ctx);
         }
-        let validity = match msg {
-            Ok(ws::Message::Binary(bin)) => match InternalMessage::from_bytes(&bin.clone()) {
-                Ok(internal_message) => self
-                    .game_wrapper
-                    .write()
-                    .unwrap()
-                    .handle_message(internal_message.main_message, &self.player_name),
-                Err(e) => {
-                    warn!("Failed to decode message: {:?}: {:?}", bin, e);
-                    MoveValidity::Invalid(format!("Failed to decode message: {:?}", e))
-                }
-            },
+        match msg {
+            Ok(ws::Message::Ping(ping)) => {
+                debug!("Ponging: {:?}", ping);
+                ctx.pong(&ping);
+                return;
+            }
+            Ok(ws::Message::Pong(_)) => return,
+            Ok(ws::Message::Close(reason)) => {
+                debug!(
+                    "Player {} closed the connection: {:?}",
+                    self.player_name.0, reason
+                );
+                ctx.close(reason);
+                ctx.stop();
+                return;
+            }
+            _ => (),
+        }
+        let decoded = match msg {
+            Ok(ws::Message::Binary(bin)) => Some(InternalMessage::from_bytes(&bin)),
+            Ok(ws::Message::Text(text)) => Some(InternalMessage::from_json(&text)),
             wildcard => {
                 warn!("Unexpected message received: {:?}", wildcard);
-                MoveValidity::Invalid("Unexpected message received".to_string())
+                None
             }
         };
+        let validity = match decoded {
+            Some(decoded) => match decoded {
+                Ok(internal_message) => {
+                    let type_name = internal_message
+                        .main_message
+                        .body
+                        .as_ref()
+                        .map_or("Empty", message_type);
+                    let decision = self.rate_limiter.check(type_name, Instant::now());
+                    if decision != RateLimitDecision::Allowed {
+                        self.reject(decision, ctx);
+                        return;
+                    }
+                    if self.handle_handshake(&internal_message.main_message, ctx) {
+                        return;
+                    }
+                    if let Some(body) = &internal_message.main_message.body {
+                        let reply = self
+                            .game_wrapper
+                            .read()
+                            .unwrap()
+                            .handle_query(body, &self.player_name);
+                        if let Some(reply) = reply {
+                            self.send(&reply, ctx);
+                            return;
+                        }
+                    }
+                    let new_name = match &internal_message.main_message.body {
+                        Some(Body::RenamePlayer(rp)) => Some(PlayerName(rp.name.clone())),
+                        _ => None,
+                    };
+                    let validity = self
+                        .game_wrapper
+                        .write()
+                        .unwrap()
+                        .handle_message(internal_message.main_message, &self.player_name);
+                    // The game now knows this player by their new name, so must we.
+                    if let (MoveValidity::Valid, Some(new_name)) = (&validity, new_name) {
+                        self.player_name = new_name;
+                    }
+                    validity
+                }
+                Err(e) => {
+                    warn!("Failed to decode {:?} message: {:?}", self.wire_format, e);
+                    let reason = format!("Failed to decode message: {}", e);
+                    let decision = self.rate_limiter.violation(reason, Instant::now());
+                    self.reject(decision, ctx);
+                    return;
+                }
+            },
+            None => MoveValidity::Invalid("Unexpected message received".to_string()),
+        };
         debug!("Received move that is: {:?}", validity);
         match validity {
             MoveValidity::Valid => {
@@ -184,7 +723,7 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for MyWs {
             }
             MoveValidity::Invalid(reason) => {
                 let response = InternalMessage::from_invalid_reason(reason);
-                ctx.binary(response.to_bytes());
+                self.send(&response, ctx);
             }
         }
     }
@@ -195,10 +734,99 @@ impl Handler<InternalMessage> for MyWs {
     type Result = ();
 
     fn handle(&mut self, msg: InternalMessage, ctx: &mut Self::Context) {
-        ctx.binary(msg.to_bytes());
+        self.send(&msg, ctx);
+    }
+}
+
+fn to_ws_close_reason(close_reason: &CloseReason) -> ws::CloseReason {
+    ws::CloseReason {
+        code: ws::CloseCode::Other(close_reason.code()),
+        description: Some(close_reason.description()),
+    }
+}
+
+// This message tells an actor to close its websocket, e.g. when kicked.
+pub struct CloseConnection {
+    pub reason: CloseReason,
+}
+
+impl Message for CloseConnection {
+    type Result = ();
+}
+
+impl Handler<CloseConnection> for MyWs {
+    type Result = ();
+
+    fn handle(&mut self, msg: CloseConnection, ctx: &mut Self::Context) {
+        self.close(msg.reason, ctx);
+    }
+}
+
+/// Sends the lobby as JSON whenever it changes. Games change under many
+/// different locks, so rather than have them all tell us, we check often.
+pub struct LobbyWs {
+    game_manager_wrapper: web::Data<GameManagerWrapper>,
+    last_sent: Option<String>,
+}
+
+impl LobbyWs {
+    fn send_lobby(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        let lobby_games = self
+            .game_manager_wrapper
+            .game_manager
+            .read()
+            .unwrap()
+            .get_lobby_games();
+        let json = serde_json::to_string(&lobby_games).unwrap();
+        if self.last_sent.as_ref() != Some(&json) {
+            ctx.text(json.clone());
+            self.last_sent = Some(json);
+        }
+    }
+}
+
+impl Actor for LobbyWs {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.send_lobby(ctx);
+        ctx.run_interval(LOBBY_INTERVAL, |act, ctx| act.send_lobby(ctx));
     }
 }
 
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for LobbyWs {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(ping)) => ctx.pong(&ping),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            // The lobby is read only.
+            _ => (),
+        }
+    }
+}
+
+/// Accepts a websocket only to close it straight away, for players who
+/// couldn't join, so the client can see why.
+pub struct RejectedWs {
+    close_reason: CloseReason,
+}
+
+impl Actor for RejectedWs {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.close(Some(to_ws_close_reason(&self.close_reason)));
+        ctx.stop();
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for RejectedWs {
+    fn handle(&mut self, _msg: Result<ws::Message, ws::ProtocolError>, _ctx: &mut Self::Context) {}
+}
+
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
     HttpServer::new(|| App::new().route("/ws/", web::get().to(index)))
@@ -1,22 +1,29 @@
 use anyhow::{anyhow, Result};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::convert::TryInto;
 
+use crate::chat::is_non_verbal_signal;
+use crate::command::parse_command;
 use crate::game_state::GameState;
+use crate::hint::{suggest_hint, DEFAULT_HINT_COOLDOWN_SECS};
 use crate::load_map;
+use crate::planner::plan_path;
 use crate::types::main_message::Body;
 use crate::types::{
-    Ability, GameStatus, Heister, HeisterColor, Internal, MainMessage, MapPosition, Move,
-    MoveDirection, PlaceTile, PlayerName, Square, SquareType, Tile, ESCAPED, TIMER_DURATION_SECS,
+    Ability, AddBot, Command, GameStatus, Heister, HeisterColor, Hint, Internal, KickPlayer,
+    MainMessage, MapPosition, Move, MoveDirection, Nudge, PathPlan, Ping, PlaceTile, PlanPath,
+    PlayerName, PossibleMoves, QueryMoves, RemoveBot, RenamePlayer, Square, SquareType, Tile,
+    TransferHost, TryInternal, ESCAPED, TIMER_DURATION_SECS,
 };
 use crate::utils::get_current_time_secs;
 
 use log::{debug, info};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Game {
     pub game_handle: GameHandle,
     pub game_options: GameOptions,
@@ -24,21 +31,31 @@ pub struct Game {
     pub tile_deck: Vec<Tile>,
     pub game_created: u64,
     revealed_teleporters: HashMap<HeisterColor, Vec<MapPosition>>,
+    last_hint_time: u64,
 }
 #[derive(Clone, Default, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct GameHandle(pub String);
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct GameOptions {
     pub shuffle_tiles: bool,
+    // If set, the deck is always shuffled the same way for the same seed.
+    pub seed: Option<u64>,
     pub teleport_only_from_portal: bool,
+    // If set, players may only chat right after a timer flip.
+    pub enforce_silence: bool,
+    // How long the team has to wait between hints.
+    pub hint_cooldown_secs: u64,
 }
 
 impl Default for GameOptions {
     fn default() -> Self {
         GameOptions {
             shuffle_tiles: true,
+            seed: None,
             teleport_only_from_portal: false,
+            enforce_silence: false,
+            hint_cooldown_secs: DEFAULT_HINT_COOLDOWN_SECS,
         }
     }
 }
@@ -67,8 +84,10 @@ impl Game {
         let game_state = GameState::new(game_handle.clone());
         let mut tile_deck: Vec<Tile> = load_map::load_tiles_from_json();
         if game_options.shuffle_tiles {
-            let mut rng = thread_rng();
-            tile_deck.shuffle(&mut rng);
+            match game_options.seed {
+                Some(seed) => tile_deck.shuffle(&mut StdRng::seed_from_u64(seed)),
+                None => tile_deck.shuffle(&mut thread_rng()),
+            }
         }
         let game_created = get_current_time_secs();
         // NOTE: Assumption: All games start with only one tile revealed
@@ -84,6 +103,7 @@ impl Game {
             tile_deck,
             game_created,
             revealed_teleporters,
+            last_hint_time: 0,
         }
     }
 
@@ -119,6 +139,24 @@ impl Game {
         MoveValidity::Valid
     }
 
+    /// Replaces the revealed tiles with the given ones, already positioned and
+    /// rotated, and takes them out of the deck. This is for setting up a game
+    /// in a particular state, e.g. in tests.
+    pub fn set_tiles(&mut self, tiles: Vec<Tile>) {
+        self.tile_deck
+            .retain(|t| !tiles.iter().any(|placed| placed.name == t.name));
+        self.game_state.remaining_tiles = self.tile_deck.len().try_into().unwrap();
+        self.revealed_teleporters = HashMap::new();
+        self.game_state.tiles = Vec::new();
+        for tile in tiles {
+            let new_tile_idx = self.game_state.tiles.len();
+            Self::update_revealed_teleporters(&mut self.revealed_teleporters, &tile);
+            self.game_state.tiles.push(tile);
+            self.game_state.update_tile_doors(new_tile_idx);
+        }
+        self.update_auxiliary_state();
+    }
+
     fn rotate_abilities(&mut self) {
         let mut player_abilities: Vec<Vec<Ability>> = self
             .game_state
@@ -570,6 +608,203 @@ impl Game {
         }
     }
 
+    fn validate_is_host(&self, player_name: &PlayerName) -> MoveValidity {
+        match self.game_state.is_host(player_name) {
+            true => MoveValidity::Valid,
+            false => MoveValidity::Invalid(format!(
+                "Only the host ({}) can do that",
+                self.game_state.host.0
+            )),
+        }
+    }
+
+    fn process_kick_player(&mut self, kp: KickPlayer, player_name: &PlayerName) -> MoveValidity {
+        let validity = self.validate_is_host(player_name);
+        if validity.is_invalid() {
+            return validity;
+        }
+        if self.game_state.game_status != GameStatus::Staging {
+            return MoveValidity::Invalid(
+                "Players can only be kicked before the game starts".to_string(),
+            );
+        }
+        if &kp.name == player_name {
+            return MoveValidity::Invalid("You cannot kick yourself".to_string());
+        }
+        match self.game_state.remove_player(&kp.name) {
+            Ok(_) => {
+                info!(
+                    "Player {} was kicked from {}",
+                    kp.name.0, self.game_handle.0
+                );
+                MoveValidity::Valid
+            }
+            Err(e) => MoveValidity::Invalid(format!("Couldn't kick player: {}", e)),
+        }
+    }
+
+    fn process_add_bot(&mut self, _ab: AddBot, player_name: &PlayerName) -> MoveValidity {
+        let validity = self.validate_is_host(player_name);
+        if validity.is_invalid() {
+            return validity;
+        }
+        match self.game_state.add_bot() {
+            Ok(name) => {
+                info!("Bot {} was added to {}", name.0, self.game_handle.0);
+                MoveValidity::Valid
+            }
+            Err(e) => MoveValidity::Invalid(format!("Couldn't add bot: {}", e)),
+        }
+    }
+
+    fn process_remove_bot(&mut self, rb: RemoveBot, player_name: &PlayerName) -> MoveValidity {
+        let validity = self.validate_is_host(player_name);
+        if validity.is_invalid() {
+            return validity;
+        }
+        let validity = self.game_is_staging("remove bots");
+        if validity.is_invalid() {
+            return validity;
+        }
+        if !self.game_state.is_bot(&rb.name) {
+            return MoveValidity::Invalid(format!("{} is not a bot", rb.name.0));
+        }
+        match self.game_state.remove_player(&rb.name) {
+            Ok(_) => {
+                info!("Bot {} was removed from {}", rb.name.0, self.game_handle.0);
+                MoveValidity::Valid
+            }
+            Err(e) => MoveValidity::Invalid(format!("Couldn't remove bot: {}", e)),
+        }
+    }
+
+    fn process_transfer_host(
+        &mut self,
+        th: TransferHost,
+        player_name: &PlayerName,
+    ) -> MoveValidity {
+        let validity = self.validate_is_host(player_name);
+        if validity.is_invalid() {
+            return validity;
+        }
+        if !self.has_player(&th.name.0) {
+            return MoveValidity::Invalid(format!("Player {} is not in this game", th.name.0));
+        }
+        if self.game_state.is_bot(&th.name) {
+            return MoveValidity::Invalid("Bots cannot be the host".to_string());
+        }
+        self.game_state.host = th.name;
+        MoveValidity::Valid
+    }
+
+    fn game_is_staging(&self, what: &str) -> MoveValidity {
+        match self.game_state.game_status {
+            GameStatus::Staging => MoveValidity::Valid,
+            _ => MoveValidity::Invalid(format!("You can only {} before the game starts", what)),
+        }
+    }
+
+    fn process_leave_game(&mut self, player_name: &PlayerName) -> MoveValidity {
+        let validity = self.game_is_staging("leave");
+        if validity.is_invalid() {
+            return validity;
+        }
+        match self.game_state.remove_player(player_name) {
+            Ok(_) => {
+                info!("Player {} left game {}", player_name.0, self.game_handle.0);
+                MoveValidity::Valid
+            }
+            Err(e) => MoveValidity::Invalid(format!("Couldn't leave game: {}", e)),
+        }
+    }
+
+    fn process_rename_player(
+        &mut self,
+        rp: RenamePlayer,
+        player_name: &PlayerName,
+    ) -> MoveValidity {
+        let validity = self.game_is_staging("change your name");
+        if validity.is_invalid() {
+            return validity;
+        }
+        let validity = self.game_state.validate_player_name(&rp.name);
+        if validity.is_invalid() {
+            return validity;
+        }
+        match self.game_state.rename_player(player_name, rp.name) {
+            Ok(_) => MoveValidity::Valid,
+            Err(e) => MoveValidity::Invalid(format!("Couldn't rename player: {}", e)),
+        }
+    }
+
+    /// When the host disconnects, hand the role to the first player in join
+    /// order who is still connected. If nobody is, the host stays as is so
+    /// they get it back if they reconnect.
+    pub fn migrate_host(&mut self, connected_players: &[PlayerName]) {
+        let new_host = self
+            .game_state
+            .players
+            .iter()
+            .filter(|p| !p.is_bot)
+            .map(|p| PlayerName(p.name.clone()))
+            .find(|n| n != &self.game_state.host && connected_players.contains(n));
+        if let Some(new_host) = new_host {
+            info!(
+                "Host {} left game {}, {} is now the host",
+                self.game_state.host.0, self.game_handle.0, new_host.0
+            );
+            self.game_state.host = new_host;
+        }
+    }
+
+    /// If the game enforces silent play, players may only chat while the game
+    /// is underway if they just flipped a timer. Non-verbal signals are always
+    /// allowed.
+    pub fn validate_chat(&self, text: &str) -> MoveValidity {
+        if !self.game_options.enforce_silence
+            || self.game_state.players_may_speak
+            || self.game_state.game_status != GameStatus::Ongoing
+            || is_non_verbal_signal(text)
+        {
+            return MoveValidity::Valid;
+        }
+        MoveValidity::Invalid(
+            "You may not speak right now! Flip a timer to be able to talk again".to_string(),
+        )
+    }
+
+    /// Nudges are allowed even when players may not speak, but they must
+    /// point at a real player and, if given, a real square.
+    pub fn validate_nudge(&self, nudge: &Nudge) -> MoveValidity {
+        if !self.has_player(&nudge.target_player.0) {
+            return MoveValidity::Invalid(format!(
+                "Player {} is not in this game",
+                nudge.target_player.0
+            ));
+        }
+        match &nudge.map_position {
+            Some(map_position) => self.validate_position_on_map(map_position),
+            None => MoveValidity::Valid,
+        }
+    }
+
+    /// Pings are allowed even when players may not speak, but they must point
+    /// at a real square.
+    pub fn validate_ping(&self, ping: &Ping) -> MoveValidity {
+        self.validate_position_on_map(&ping.map_position)
+    }
+
+    fn validate_position_on_map(&self, map_position: &MapPosition) -> MoveValidity {
+        match self
+            .game_state
+            .get_absolute_grid()
+            .contains_key(map_position)
+        {
+            true => MoveValidity::Valid,
+            false => MoveValidity::Invalid(format!("Position {:?} not on map", map_position)),
+        }
+    }
+
     fn game_is_ongoing(&self) -> MoveValidity {
         match &self.game_state.game_status {
             GameStatus::Ongoing | GameStatus::PreFirstMove => MoveValidity::Valid,
@@ -583,6 +818,90 @@ impl Game {
         }
     }
 
+    /// Where the given heister could be moved to by the given player right now.
+    /// Outside of an ongoing game there is nowhere to go.
+    pub fn query_moves(
+        &self,
+        query_moves: QueryMoves,
+        player_name: &PlayerName,
+    ) -> Result<PossibleMoves> {
+        let player = match self
+            .game_state
+            .players
+            .iter()
+            .find(|p| p.name == player_name.0)
+        {
+            Some(player) => player,
+            None => return Err(anyhow!("You are not a player in this game")),
+        };
+        let positions = match self.game_is_ongoing() {
+            MoveValidity::Valid => self.game_state.get_reachable_positions(
+                query_moves.heister_color,
+                &player.abilities,
+                self.game_options.teleport_only_from_portal,
+            ),
+            MoveValidity::Invalid(_) => vec![],
+        };
+        Ok(PossibleMoves {
+            heister_color: query_moves.heister_color,
+            positions,
+        })
+    }
+
+    /// The shortest way the team could get the heister to the target.
+    pub fn plan_path(
+        &self,
+        plan_path_query: PlanPath,
+        player_name: &PlayerName,
+    ) -> Result<PathPlan> {
+        if !self.has_player(&player_name.0) {
+            return Err(anyhow!("You are not a player in this game"));
+        }
+        let steps = match self.game_is_ongoing() {
+            MoveValidity::Valid => plan_path(
+                &self.game_state,
+                plan_path_query.heister_color,
+                &plan_path_query.target,
+                self.game_options.teleport_only_from_portal,
+            ),
+            MoveValidity::Invalid(_) => None,
+        };
+        Ok(PathPlan {
+            heister_color: plan_path_query.heister_color,
+            target: plan_path_query.target,
+            reachable: steps.is_some(),
+            steps: steps.unwrap_or_default(),
+        })
+    }
+
+    /// Suggest the next action to the team, at most once per cooldown.
+    pub fn request_hint(&mut self, player_name: &PlayerName) -> Result<Hint> {
+        if !self.has_player(&player_name.0) {
+            return Err(anyhow!("You are not a player in this game"));
+        }
+        if let MoveValidity::Invalid(reason) = self.game_is_ongoing() {
+            return Err(anyhow!(reason));
+        }
+        let now = get_current_time_secs();
+        let next_hint_time = self.last_hint_time + self.game_options.hint_cooldown_secs;
+        if self.last_hint_time != 0 && now < next_hint_time {
+            return Err(anyhow!(
+                "You can ask for another hint in {} seconds",
+                next_hint_time - now
+            ));
+        }
+        let hint = match suggest_hint(
+            &self.game_state,
+            self.game_options.teleport_only_from_portal,
+        ) {
+            Some(hint) => hint,
+            None => return Err(anyhow!("There are no hints right now")),
+        };
+        self.last_hint_time = now;
+        self.game_state.stats.num_hints += 1;
+        Ok(hint)
+    }
+
     pub fn handle_message(
         &mut self,
         message: MainMessage,
@@ -592,15 +911,38 @@ impl Game {
         debug!("Received message: {:?}", message);
         self.pre_update_auxilliary_state();
         let body = message.body.unwrap();
+        if !self.has_player(&player_name.0) {
+            return MoveValidity::Invalid("You are not a player in this game".to_string());
+        }
+        // Commands are just another way of writing a Move or PlaceTile.
+        let body = match body {
+            Body::Command(c) => match parse_command(&Command::from_proto(c).text, &self.game_state)
+            {
+                Ok(body) => body,
+                Err(e) => return MoveValidity::Invalid(e.to_string()),
+            },
+            body => body,
+        };
         let validity = match body {
-            Body::StartGame(_) => self.start_game(),
+            Body::StartGame(_) => {
+                let is_host = self.validate_is_host(&player_name);
+                match is_host {
+                    MoveValidity::Invalid(_) => return is_host,
+                    MoveValidity::Valid => {}
+                }
+                self.start_game()
+            }
             Body::Move(m) => {
                 let valid_game_state = self.game_is_ongoing();
                 match valid_game_state {
                     MoveValidity::Invalid(_) => return valid_game_state,
                     MoveValidity::Valid => {}
                 }
-                self.process_move(Move::from_proto(m), &player_name)
+                let validity = self.process_move(Move::from_proto(m), &player_name);
+                if validity.is_valid() {
+                    self.game_state.stats.num_moves += 1;
+                }
+                validity
             }
             Body::PlaceTile(pt) => {
                 let valid_game_state = self.game_is_ongoing();
@@ -608,7 +950,11 @@ impl Game {
                     MoveValidity::Invalid(_) => return valid_game_state,
                     MoveValidity::Valid => {}
                 }
-                self.process_tile_placement(PlaceTile::from_proto(pt), &player_name)
+                let validity = self.process_tile_placement(PlaceTile::from_proto(pt), &player_name);
+                if validity.is_valid() {
+                    self.game_state.stats.num_tiles_placed += 1;
+                }
+                validity
             }
             Body::GameState(_gs) => {
                 MoveValidity::Invalid("GameState Message is invalid from players".to_string())
@@ -617,8 +963,65 @@ impl Game {
                 MoveValidity::Invalid("InvalidRequest Message is invalid from players".to_string())
             }
             Body::Chat(_c) => MoveValidity::Valid,
+            Body::Nudge(n) => match Nudge::from_proto(n) {
+                Ok(nudge) => self.validate_nudge(&nudge),
+                Err(e) => MoveValidity::Invalid(e.to_string()),
+            },
+            Body::Ping(p) => match Ping::from_proto(p) {
+                Ok(ping) => self.validate_ping(&ping),
+                Err(e) => MoveValidity::Invalid(e.to_string()),
+            },
+            Body::ChatMessage(_cm) => {
+                MoveValidity::Invalid("ChatMessage Message is invalid from players".to_string())
+            }
+            Body::KickPlayer(kp) => {
+                self.process_kick_player(KickPlayer::from_proto(kp), &player_name)
+            }
+            Body::TransferHost(th) => {
+                self.process_transfer_host(TransferHost::from_proto(th), &player_name)
+            }
+            Body::AddBot(ab) => self.process_add_bot(AddBot::from_proto(ab), &player_name),
+            Body::RemoveBot(rb) => self.process_remove_bot(RemoveBot::from_proto(rb), &player_name),
+            Body::LeaveGame(_) => self.process_leave_game(&player_name),
+            Body::RenamePlayer(rp) => {
+                self.process_rename_player(RenamePlayer::from_proto(rp), &player_name)
+            }
+            Body::QueryMoves(_qm) => {
+                MoveValidity::Invalid("QueryMoves does not change the game".to_string())
+            }
+            Body::PossibleMoves(_pm) => {
+                MoveValidity::Invalid("PossibleMoves Message is invalid from players".to_string())
+            }
+            Body::PlanPath(_pp) => {
+                MoveValidity::Invalid("PlanPath does not change the game".to_string())
+            }
+            Body::PathPlan(_pp) => {
+                MoveValidity::Invalid("PathPlan Message is invalid from players".to_string())
+            }
+            Body::RequestHint(_rh) => {
+                MoveValidity::Invalid("Hints must be requested with request_hint".to_string())
+            }
+            Body::Hint(_h) => {
+                MoveValidity::Invalid("Hint Message is invalid from players".to_string())
+            }
+            Body::Command(_c) => {
+                MoveValidity::Invalid("Command should have been parsed already".to_string())
+            }
+            Body::Hello(_h) => {
+                MoveValidity::Invalid("Hello is only valid as the first message".to_string())
+            }
+            Body::ServerHello(_sh) => {
+                MoveValidity::Invalid("ServerHello Message is invalid from players".to_string())
+            }
         };
         self.update_auxiliary_state();
+        if validity.is_valid() {
+            debug!(
+                "Board for {}:\n{}",
+                self.game_handle.0,
+                self.game_state.render_ascii()
+            );
+        }
 
         validity
     }
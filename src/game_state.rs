@@ -9,12 +9,21 @@ use std::collections::HashMap;
 use std::convert::From;
 
 use crate::types::{
-    get_wall_color, proto_types, GameStatus, Heister, HeisterColor, Internal, MapPosition,
-    MoveDirection, Player, PossibleTeleportEntry, Square, SquareType, StartingTile, Tile, WallType,
-    TIMER_DURATION_SECS,
+    get_heister_letter, get_wall_color, proto_types, Ability, GameStats, GameStatus, Heister,
+    HeisterColor, Internal, MapPosition, MoveDirection, Player, PlayerName, PossibleTeleportEntry,
+    Presence, Square, SquareType, StartingTile, Tile, WallType, DIRECTIONS, TIMER_DURATION_SECS,
 };
 
-const MAX_PLAYERS: usize = 8;
+pub const MAX_PLAYERS: usize = 8;
+pub const MAX_PLAYER_NAME_LENGTH: usize = 24;
+
+/// A square a heister can be moved to in one move, and the ability needed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PossibleMove {
+    pub position: MapPosition,
+    pub ability: Ability,
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct GameState {
     pub game_name: GameHandle,
@@ -30,6 +39,8 @@ pub struct GameState {
     pub possible_escalators: HashMap<HeisterColor, MapPosition>,
     pub possible_teleports: HashMap<HeisterColor, Vec<MapPosition>>,
     pub players_may_speak: bool,
+    pub host: PlayerName,
+    pub stats: GameStats,
 }
 
 impl Internal for GameState {
@@ -98,6 +109,8 @@ impl Internal for GameState {
             possible_escalators,
             possible_teleports,
             players_may_speak: proto.players_may_speak,
+            host: PlayerName(proto.host),
+            stats: proto.stats.map(GameStats::from_proto).unwrap_or_default(),
         }
     }
 
@@ -141,6 +154,8 @@ impl Internal for GameState {
             possible_escalators,
             possible_teleports,
             players_may_speak: self.players_may_speak,
+            host: self.host.0.to_string(),
+            stats: Some(self.stats.to_proto()),
         }
     }
 }
@@ -185,6 +200,8 @@ impl GameState {
             possible_escalators,
             possible_teleports,
             players_may_speak: true,
+            host: PlayerName::default(),
+            stats: GameStats::default(),
         }
     }
 
@@ -194,13 +211,117 @@ impl GameState {
             // don't actually register the player.
             return Ok(());
         }
+        // If nobody was named host when the game was created, or whoever was
+        // hasn't joined, the first player in is. Otherwise nobody could start
+        // the game.
+        if !self.has_player(&self.host.0) {
+            self.host = PlayerName(name.clone());
+        }
+        // They're online once their connection is registered.
         self.players.push(Player {
             name,
             abilities: vec![],
+            is_bot: false,
+            presence: Presence::Offline,
+        });
+        Ok(())
+    }
+
+    /// Returns whether the player's presence changed, so callers know whether
+    /// to push the new state.
+    pub fn set_presence(&mut self, name: &PlayerName, presence: Presence) -> bool {
+        match self.players.iter_mut().find(|p| p.name == name.0) {
+            Some(player) if player.presence != presence => {
+                player.presence = presence;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.players.len() >= MAX_PLAYERS
+    }
+
+    /// Add a bot player, named "Bot 1", "Bot 2", etc. Bots are never the host.
+    pub fn add_bot(&mut self) -> Result<PlayerName> {
+        if self.game_status != GameStatus::Staging {
+            return Err(anyhow!("Bots can only be added before the game starts"));
+        }
+        if self.players.len() >= MAX_PLAYERS {
+            return Err(anyhow!("The game is full"));
+        }
+        let name = (1..)
+            .map(|i| format!("Bot {}", i))
+            .find(|name| !self.has_player(name))
+            .unwrap();
+        self.players.push(Player {
+            name: name.clone(),
+            abilities: vec![],
+            is_bot: true,
+            presence: Presence::Online,
         });
+        Ok(PlayerName(name))
+    }
+
+    pub fn is_bot(&self, name: &PlayerName) -> bool {
+        self.players.iter().any(|p| p.name == name.0 && p.is_bot)
+    }
+
+    /// Remove a player from the game. If they were the host, the next human
+    /// player in join order becomes the host.
+    pub fn remove_player(&mut self, name: &PlayerName) -> Result<()> {
+        let num_players = self.players.len();
+        self.players.retain(|p| p.name != name.0);
+        if self.players.len() == num_players {
+            return Err(anyhow!("Player {} is not in game", name.0));
+        }
+        if &self.host == name {
+            self.host = match self.players.iter().find(|p| !p.is_bot) {
+                Some(p) => PlayerName(p.name.clone()),
+                None => PlayerName::default(),
+            };
+        }
         Ok(())
     }
 
+    /// Check that a name could be used by a new or renamed player.
+    pub fn validate_player_name(&self, name: &PlayerName) -> MoveValidity {
+        let trimmed = name.0.trim();
+        if trimmed.is_empty() {
+            return MoveValidity::Invalid("Name cannot be empty".to_string());
+        }
+        if trimmed != name.0 {
+            return MoveValidity::Invalid("Name cannot start or end with spaces".to_string());
+        }
+        if name.0.chars().count() > MAX_PLAYER_NAME_LENGTH {
+            return MoveValidity::Invalid(format!(
+                "Name cannot be longer than {} characters",
+                MAX_PLAYER_NAME_LENGTH
+            ));
+        }
+        if self.has_player(&name.0) {
+            return MoveValidity::Invalid(format!("Name {} is already taken", name.0));
+        }
+        MoveValidity::Valid
+    }
+
+    pub fn rename_player(&mut self, old_name: &PlayerName, new_name: PlayerName) -> Result<()> {
+        let player = match self.players.iter_mut().find(|p| p.name == old_name.0) {
+            Some(p) => p,
+            None => return Err(anyhow!("Player {} is not in game", old_name.0)),
+        };
+        player.name = new_name.0.clone();
+        if &self.host == old_name {
+            self.host = new_name;
+        }
+        Ok(())
+    }
+
+    pub fn is_host(&self, name: &PlayerName) -> bool {
+        &self.host == name
+    }
+
     pub fn has_player(&self, name: &str) -> bool {
         for p in self.players.iter() {
             if p.name == name {
@@ -236,6 +357,64 @@ impl GameState {
         grid
     }
 
+    /// Draws the whole board as text, using the same characters as Tile::pp,
+    /// with each heister as a capital letter in the middle of its square.
+    /// Heisters that have escaped are listed underneath instead.
+    pub fn render_ascii(&self) -> String {
+        let grid = self.get_absolute_grid();
+        if grid.is_empty() {
+            return String::new();
+        }
+        let min_x = grid.keys().map(|p| p.x).min().unwrap();
+        let max_x = grid.keys().map(|p| p.x).max().unwrap();
+        let min_y = grid.keys().map(|p| p.y).min().unwrap();
+        let max_y = grid.keys().map(|p| p.y).max().unwrap();
+        let width = ((max_x - min_x + 1) * 3) as usize;
+        let height = ((max_y - min_y + 1) * 3) as usize;
+        let mut canvas = vec![vec![' '; width]; height];
+        // The top left corner of a square on the canvas.
+        let offset = |pos: &MapPosition| {
+            (
+                ((pos.y - min_y) * 3) as usize,
+                ((pos.x - min_x) * 3) as usize,
+            )
+        };
+
+        for (pos, square) in grid.iter() {
+            let (row, col) = offset(pos);
+            let lines = [
+                square.pp_top_row(),
+                square.pp_middle_row(),
+                square.pp_bottom_row(),
+            ];
+            for (i, line) in lines.iter().enumerate() {
+                for (j, c) in line.chars().enumerate() {
+                    canvas[row + i][col + j] = c;
+                }
+            }
+        }
+
+        let mut escaped = Vec::new();
+        for heister in self.heisters.iter() {
+            let letter = get_heister_letter(heister.heister_color);
+            if heister.has_escaped {
+                escaped.push(letter.to_string());
+            } else if grid.contains_key(&heister.map_position) {
+                let (row, col) = offset(&heister.map_position);
+                canvas[row + 1][col + 1] = letter;
+            }
+        }
+
+        let mut pp = String::new();
+        for row in canvas {
+            pp += format!("{}\n", row.iter().collect::<String>()).as_str();
+        }
+        if !escaped.is_empty() {
+            pp += format!("Escaped: {}\n", escaped.join(" ")).as_str();
+        }
+        pp
+    }
+
     // NOTE: Would be nice if self.game_state.heisters was a map<color, heister>
     // or even <color, pos>
     pub fn get_mut_heister_from_vec(&mut self, hc: HeisterColor) -> Option<&mut Heister> {
@@ -643,4 +822,150 @@ impl GameState {
             }
         }
     }
+
+    /// Whether a heister other than the given one is on the position.
+    fn occupied_by_other(&self, heister_color: HeisterColor, position: &MapPosition) -> bool {
+        self.heisters
+            .iter()
+            .any(|h| h.heister_color != heister_color && &h.map_position == position)
+    }
+
+    /// Whether the heister at `pos` could slide one square on to the adjacent `next`.
+    fn can_step(
+        &self,
+        grid: &HashMap<MapPosition, Square>,
+        heister_color: HeisterColor,
+        pos: &MapPosition,
+        next: &MapPosition,
+    ) -> bool {
+        match grid.get(next) {
+            Some(square) if square.square_type != SquareType::Filled => (),
+            _ => return false,
+        }
+        self.adjacent_move_blocked_by_wall(grid, pos, next)
+            .is_valid()
+            && !self.occupied_by_other(heister_color, next)
+    }
+
+    /// If the position is an escalator, where it leads.
+    pub fn get_escalator_destination(
+        &self,
+        grid: &HashMap<MapPosition, Square>,
+        pos: &MapPosition,
+    ) -> Option<MapPosition> {
+        match grid.get(pos) {
+            Some(square) if square.square_type == SquareType::Escalator => {
+                let (_idx, tile) = self.get_index_and_tile(pos)?;
+                tile.get_escalator_dest(pos)
+            }
+            _ => None,
+        }
+    }
+
+    /// Every square the heister could be moved to in one move by a player with
+    /// the given abilities, along with the ability each move needs. This follows
+    /// the same rules as Game::process_move: slides in a straight line as far as
+    /// walls, doors and other heisters allow, plus escalators and teleports.
+    pub fn get_possible_moves(
+        &self,
+        heister_color: HeisterColor,
+        abilities: &[Ability],
+        teleport_only_from_portal: bool,
+    ) -> Vec<PossibleMove> {
+        let heister_pos = match self.get_heister_from_vec(heister_color) {
+            Some(h) if !h.has_escaped => h.map_position,
+            _ => return vec![],
+        };
+        let grid = self.get_absolute_grid();
+        self.get_possible_moves_from(
+            &grid,
+            heister_color,
+            heister_pos,
+            abilities,
+            teleport_only_from_portal,
+        )
+    }
+
+    /// Like get_possible_moves, but as if the heister were at `heister_pos`,
+    /// with every other heister staying where it is. Used for path planning.
+    pub fn get_possible_moves_from(
+        &self,
+        grid: &HashMap<MapPosition, Square>,
+        heister_color: HeisterColor,
+        heister_pos: MapPosition,
+        abilities: &[Ability],
+        teleport_only_from_portal: bool,
+    ) -> Vec<PossibleMove> {
+        let mut possible_moves = Vec::new();
+
+        for dir in DIRECTIONS.iter() {
+            let ability = dir.ability();
+            if !abilities.contains(&ability) {
+                continue;
+            }
+            let mut pos = heister_pos;
+            loop {
+                let next = pos.move_in_direction(dir);
+                if !self.can_step(grid, heister_color, &pos, &next) {
+                    break;
+                }
+                possible_moves.push(PossibleMove {
+                    position: next,
+                    ability,
+                });
+                pos = next;
+            }
+        }
+
+        if abilities.contains(&Ability::UseEscalator) {
+            if let Some(dest) = self.get_escalator_destination(grid, &heister_pos) {
+                if !self.occupied_by_other(heister_color, &dest) {
+                    possible_moves.push(PossibleMove {
+                        position: dest,
+                        ability: Ability::UseEscalator,
+                    });
+                }
+            }
+        }
+
+        // Teleports are disabled once all the items have been taken.
+        let on_own_teleport = match grid.get(&heister_pos) {
+            Some(square) => square.teleport_matches_color(heister_color),
+            None => false,
+        };
+        if abilities.contains(&Ability::Teleport)
+            && !self.all_items_taken
+            && (on_own_teleport || !teleport_only_from_portal)
+        {
+            if let Some(teleports) = self.possible_teleports.get(&heister_color) {
+                for dest in teleports {
+                    if dest != &heister_pos && !self.occupied_by_other(heister_color, dest) {
+                        possible_moves.push(PossibleMove {
+                            position: *dest,
+                            ability: Ability::Teleport,
+                        });
+                    }
+                }
+            }
+        }
+        possible_moves
+    }
+
+    /// Like get_possible_moves, but just the distinct destinations.
+    pub fn get_reachable_positions(
+        &self,
+        heister_color: HeisterColor,
+        abilities: &[Ability],
+        teleport_only_from_portal: bool,
+    ) -> Vec<MapPosition> {
+        let mut positions: Vec<MapPosition> = Vec::new();
+        for possible_move in
+            self.get_possible_moves(heister_color, abilities, teleport_only_from_portal)
+        {
+            if !positions.contains(&possible_move.position) {
+                positions.push(possible_move.position);
+            }
+        }
+        positions
+    }
 }
@@ -0,0 +1,94 @@
+// Suggests what the team could do next when they are stuck.
+
+use crate::game_state::GameState;
+use crate::planner::plan_to_nearest_goal;
+use crate::types::{Ability, HeisterColor, Hint, MapPosition, Square, SquareType};
+
+use std::collections::HashMap;
+
+pub const DEFAULT_HINT_COOLDOWN_SECS: u64 = 30;
+
+fn color_name(heister_color: HeisterColor) -> String {
+    format!("{:?}", heister_color).to_lowercase()
+}
+
+fn describe_position(grid: &HashMap<MapPosition, Square>, position: &MapPosition) -> String {
+    let square = match grid.get(position) {
+        Some(square) => square,
+        None => return format!("({}, {})", position.x, position.y),
+    };
+    if square.square_type == SquareType::Escalator {
+        "the escalator".to_string()
+    } else if square.square_type == SquareType::TimerFlip {
+        "the timer".to_string()
+    } else if square.is_item() {
+        "the item".to_string()
+    } else if square.is_escape() {
+        "the exit".to_string()
+    } else if square.is_teleport() {
+        "the teleporter".to_string()
+    } else if square.has_door() {
+        "the door".to_string()
+    } else {
+        format!("({}, {})", position.x, position.y)
+    }
+}
+
+fn describe_move(
+    grid: &HashMap<MapPosition, Square>,
+    heister_color: HeisterColor,
+    position: &MapPosition,
+    ability: Ability,
+) -> String {
+    let heister = color_name(heister_color);
+    let destination = describe_position(grid, position);
+    let action = match ability {
+        Ability::UseEscalator => {
+            format!("take {} along the escalator to {}", heister, destination)
+        }
+        Ability::Teleport => format!("teleport {} to {}", heister, destination),
+        _ => format!("slide {} to {}", heister, destination),
+    };
+    format!("Someone with {:?} should {}", ability, action)
+}
+
+/// The next useful thing the team could do, if we can find one. Revealing a
+/// tile comes first, since a heister only stops at a door for that. Otherwise
+/// it's the first step for whichever heister is closest to its next goal.
+pub fn suggest_hint(game_state: &GameState, teleport_only_from_portal: bool) -> Option<Hint> {
+    if game_state.remaining_tiles > 0 {
+        if let Some(tile_entrance) = game_state.possible_placements.first() {
+            let ability = Ability::RevealTiles;
+            return Some(Hint {
+                ability,
+                text: format!("Someone with {:?} should reveal a new tile", ability),
+                heister_color: None,
+                map_position: *tile_entrance,
+            });
+        }
+    }
+
+    let best = game_state
+        .heisters
+        .iter()
+        .filter(|h| !h.has_escaped)
+        .filter_map(|h| {
+            plan_to_nearest_goal(game_state, h, teleport_only_from_portal)
+                .map(|steps| (h.heister_color, steps))
+        })
+        .min_by_key(|(_, steps)| steps.len());
+    let (heister_color, steps) = best?;
+    let first_step = &steps[0];
+    let grid = game_state.get_absolute_grid();
+    Some(Hint {
+        ability: first_step.ability,
+        text: describe_move(
+            &grid,
+            heister_color,
+            &first_step.position,
+            first_step.ability,
+        ),
+        heister_color: Some(heister_color),
+        map_position: first_step.position,
+    })
+}
@@ -2,13 +2,24 @@
 #[macro_use]
 extern crate lazy_static;
 
+pub mod bot;
+pub mod chat;
+pub mod close_reason;
+pub mod command;
 pub mod endpoints;
 pub mod errors;
 pub mod game;
 pub mod game_state;
+pub mod hint;
 pub mod load_map;
 pub mod manager;
+pub mod password;
 pub mod periodic;
+pub mod planner;
+pub mod rate_limit;
+pub mod scenario;
 pub mod serializer;
+pub mod solver;
+pub mod svg;
 pub mod types;
 pub mod utils;
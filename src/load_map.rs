@@ -1,6 +1,9 @@
 // Load the map from data/tiles/*.json
 
-use crate::types::{MapPosition, SerializableTile, Square, SquareType, Tile, WallType};
+use crate::types::{
+    HeisterColor, MapPosition, SerializableTile, Square, SquareType, Tile, WallType,
+};
+use anyhow::{anyhow, Result};
 use serde_json::json;
 use std::fs::File;
 use std::io::BufReader;
@@ -42,6 +45,132 @@ pub fn print_tile_json() -> () {
     println!("\n{}\n", json!(SerializableTile::from(tile_2())));
 }
 
+const COLORS_PREFIX: &str = "colors:";
+
+/// Tile::pp draws teleport pads, items and escapes the same for every color,
+/// so tile art adds a line listing their colors in reading order, e.g.
+/// "colors: purple yellow". Everything above that line is just Tile::pp.
+pub fn tile_art(tile: &Tile) -> String {
+    let colors: Vec<String> = tile
+        .squares
+        .iter()
+        .filter_map(|s| s.color())
+        .map(|c| format!("{:?}", c).to_lowercase())
+        .collect();
+    format!("{}{} {}\n", tile.pp(), COLORS_PREFIX, colors.join(" "))
+}
+
+fn parse_wall(c: char, wall_char: char) -> Result<WallType> {
+    match c {
+        ' ' => Ok(WallType::Clear),
+        'P' => Ok(WallType::PurpleDoor),
+        'O' => Ok(WallType::OrangeDoor),
+        'Y' => Ok(WallType::YellowDoor),
+        'G' => Ok(WallType::GreenDoor),
+        c if c == wall_char => Ok(WallType::Impassable),
+        _ => Err(anyhow!("Unknown wall '{}'", c)),
+    }
+}
+
+fn parse_color(word: &str) -> Result<HeisterColor> {
+    match word {
+        "yellow" => Ok(HeisterColor::Yellow),
+        "purple" => Ok(HeisterColor::Purple),
+        "green" => Ok(HeisterColor::Green),
+        "orange" => Ok(HeisterColor::Orange),
+        _ => Err(anyhow!("Unknown color \"{}\"", word)),
+    }
+}
+
+fn parse_space(c: char, colors: &mut dyn Iterator<Item = HeisterColor>) -> Result<SquareType> {
+    let square_type = match c {
+        ' ' => SquareType::Normal,
+        'x' => SquareType::TimerFlip,
+        '*' => SquareType::TimerFlipUsed,
+        'Z' => SquareType::Escalator,
+        '&' => SquareType::Filled,
+        '@' | 'i' | 'e' => {
+            let color = match colors.next() {
+                Some(color) => color,
+                None => return Err(anyhow!("Not enough colors for the '{}' squares", c)),
+            };
+            match (c, color) {
+                ('@', HeisterColor::Yellow) => SquareType::YellowTeleportPad,
+                ('@', HeisterColor::Purple) => SquareType::PurpleTeleportPad,
+                ('@', HeisterColor::Green) => SquareType::GreenTeleportPad,
+                ('@', HeisterColor::Orange) => SquareType::OrangeTeleportPad,
+                ('i', HeisterColor::Yellow) => SquareType::YellowItem,
+                ('i', HeisterColor::Purple) => SquareType::PurpleItem,
+                ('i', HeisterColor::Green) => SquareType::GreenItem,
+                ('i', HeisterColor::Orange) => SquareType::OrangeItem,
+                ('e', HeisterColor::Yellow) => SquareType::YellowEscape,
+                ('e', HeisterColor::Purple) => SquareType::PurpleEscape,
+                ('e', HeisterColor::Green) => SquareType::GreenEscape,
+                ('e', HeisterColor::Orange) => SquareType::OrangeEscape,
+                _ => unreachable!(),
+            }
+        }
+        _ => return Err(anyhow!("Unknown square '{}'", c)),
+    };
+    Ok(square_type)
+}
+
+/// The inverse of tile_art, turning the ASCII drawing of a tile back into a
+/// Tile at (0, 0) with no rotations.
+pub fn parse_tile_art(art: &str, name: &str) -> Result<Tile> {
+    let mut rows: Vec<Vec<char>> = Vec::new();
+    let mut colors = Vec::new();
+    for line in art.lines() {
+        if let Some(words) = line.strip_prefix(COLORS_PREFIX) {
+            for word in words.split_whitespace() {
+                colors.push(parse_color(word)?);
+            }
+        } else if !line.trim().is_empty() {
+            rows.push(line.chars().collect());
+        }
+    }
+    if rows.len() != 12 {
+        return Err(anyhow!("Expected 12 rows of squares, got {}", rows.len()));
+    }
+    if let Some(row) = rows.iter().find(|row| row.len() != 12) {
+        return Err(anyhow!(
+            "Expected rows 12 characters wide, got {}",
+            row.len()
+        ));
+    }
+
+    let mut colors = colors.into_iter();
+    let mut squares: Vec<Square> = Vec::new();
+    for i in 0..16 {
+        let row = (i / 4) * 3;
+        let col = (i % 4) * 3;
+        let square = Square {
+            north_wall: parse_wall(rows[row][col + 1], '_')?,
+            east_wall: parse_wall(rows[row + 1][col + 2], '|')?,
+            south_wall: parse_wall(rows[row + 2][col + 1], '_')?,
+            west_wall: parse_wall(rows[row + 1][col], '|')?,
+            square_type: parse_space(rows[row + 1][col + 1], &mut colors)?,
+        };
+        squares.push(square);
+    }
+    if colors.next().is_some() {
+        return Err(anyhow!("More colors than colored squares"));
+    }
+
+    Ok(Tile {
+        squares,
+        position: MapPosition { x: 0, y: 0 },
+        name: name.to_string(),
+        num_rotations: 0,
+    })
+}
+
+/// Turns tile art into the JSON stored in data/tiles.
+pub fn tile_art_to_json(art: &str, name: &str) -> Result<String> {
+    let tile = parse_tile_art(art, name)?;
+    Ok(format!("{}\n", json!(SerializableTile::from(tile))))
+}
+
 pub fn tile_1a() -> Tile {
     // Generate the object for Tile 1a
     let mut my_squares: Vec<Square> = Vec::new();
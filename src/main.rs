@@ -17,8 +17,9 @@ use actix_web::{web, App, HttpServer};
 // My imports.
 use team_heist_tactics::endpoints;
 use team_heist_tactics::game::GameOptions;
-use team_heist_tactics::manager::{GameManager, GameManagerWrapper, TEST_HANDLE};
-use team_heist_tactics::periodic::reaper;
+use team_heist_tactics::manager::{CreateOptions, GameManager, GameManagerWrapper, TEST_HANDLE};
+use team_heist_tactics::periodic::{bot_runner, reaper};
+use team_heist_tactics::rate_limit::{PasswordAttemptLimiter, RateLimitConfig};
 
 const REQUIRED_ENV_VARS: &'static [&'static str] = &[
     "THT_IP_ADDRESS",
@@ -85,6 +86,11 @@ async fn main() -> std::io::Result<()> {
     let game_manager = RwLock::new(game_manager);
     let game_manager_wrapper = GameManagerWrapper { game_manager };
     let game_manager_wrapper = web::Data::new(game_manager_wrapper);
+    let rate_limit_config = web::Data::new(RateLimitConfig::from_env());
+    // Shared by every worker, so guesses can't be spread across them.
+    let password_attempt_limiter = web::Data::new(PasswordAttemptLimiter::new(
+        rate_limit_config.password_attempts,
+    ));
 
     let ip = env::var("THT_IP_ADDRESS").unwrap();
     let port = env::var("THT_PORT").unwrap();
@@ -100,9 +106,17 @@ async fn main() -> std::io::Result<()> {
         .new_game(
             GameOptions {
                 shuffle_tiles: false,
+                seed: None,
                 teleport_only_from_portal: false,
+                enforce_silence: false,
+                hint_cooldown_secs: 0,
+            },
+            CreateOptions {
+                handle: Some(TEST_HANDLE.to_string()),
+                host: None,
+                public: false,
+                password_hash: None,
             },
-            Some(TEST_HANDLE.to_string()),
         )
         .unwrap();
 
@@ -112,13 +126,33 @@ async fn main() -> std::io::Result<()> {
         reaper(&reaper_game_manager_wrapper);
     });
 
+    let bot_game_manager_wrapper = game_manager_wrapper.clone();
+    // Bot thread
+    thread::spawn(move || loop {
+        bot_runner(&bot_game_manager_wrapper);
+    });
+
     HttpServer::new(move || {
         let app = App::new()
             .app_data(game_manager_wrapper.clone())
+            .app_data(rate_limit_config.clone())
+            .app_data(password_attempt_limiter.clone())
             .route("/", web::get().to(endpoints::index))
             .route("/play", web::get().to(endpoints::play))
             .route("/create_game", web::post().to(endpoints::create_game))
-            .route("/play_game", web::get().to(endpoints::play_game));
+            .route("/play_game", web::get().to(endpoints::play_game))
+            .route(
+                "/games/{handle}/board.svg",
+                web::get().to(endpoints::board_svg),
+            )
+            .route("/lobby", web::get().to(endpoints::lobby))
+            .route("/lobby/ws", web::get().to(endpoints::lobby_ws))
+            .route("/api/games", web::get().to(endpoints::api_games))
+            .route("/api/games/{handle}", web::get().to(endpoints::api_game))
+            .route(
+                "/api/games/{handle}/players",
+                web::get().to(endpoints::api_game_players),
+            );
         let app = match deployment_mode {
             DeploymentMode::Dev => app.service(fs::Files::new("/static", "static")),
             _ => app,
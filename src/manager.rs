@@ -1,38 +1,106 @@
 // Manages all the games.
 
-use crate::endpoints::MyWs;
+use crate::bot::{PlayerAgent, SimpleBot};
+use crate::chat::{
+    ChatLog, PlayerRateLimiter, NUDGE_RATE_LIMIT_MESSAGES, NUDGE_RATE_LIMIT_WINDOW_SECS,
+    PING_RATE_LIMIT_MESSAGES, PING_RATE_LIMIT_WINDOW_SECS,
+};
+use crate::close_reason::CloseReason;
+use crate::endpoints::{CloseConnection, MyWs};
 use crate::game::{Game, GameHandle, GameOptions, MoveValidity};
+use crate::game_state::{GameState, MAX_PLAYERS};
 use crate::serializer::InternalMessage;
 use crate::types::main_message::Body;
-use crate::types::{MainMessage, PlayerName};
+use crate::types::{
+    ChatMessage, GameStatus, MainMessage, Nudge, Ping, PlanPath, PlayerName, Presence, QueryMoves,
+    TryInternal,
+};
+use crate::utils::get_current_time_secs;
 
 use actix::Addr;
 use anyhow::{anyhow, Result};
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
+use serde::Serialize;
 use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 
 pub const TEST_HANDLE: &str = "test";
 
+pub struct CreateOptions {
+    pub handle: Option<String>,
+    pub host: Option<PlayerName>,
+    // Whether the game is listed in the lobby.
+    pub public: bool,
+    // If set, players need the password to join. Made with
+    // password::hash_password, so the password itself is never kept.
+    pub password_hash: Option<String>,
+}
+
 pub struct JoinOptions {
     pub name: String,
     pub handle: GameHandle,
+    // If the game is full or has started, watch instead of failing to join.
+    pub spectate: bool,
+}
+
+/// A short description of a game, for listing games in the JSON API.
+#[derive(Debug, Serialize)]
+pub struct GameSummary {
+    pub handle: String,
+    pub game_status: GameStatus,
+    pub host: String,
+    pub num_players: usize,
+    pub max_players: usize,
+    pub game_created: u64,
+    pub public: bool,
+    pub has_password: bool,
+    pub enforce_silence: bool,
+    pub teleport_only_from_portal: bool,
 }
 
 #[derive(Debug)]
 pub struct GameWrapper {
     game: Game,
-    actors: Vec<Addr<MyWs>>,
+    actors: HashMap<PlayerName, Addr<MyWs>>,
+    chat_log: ChatLog,
+    public: bool,
+    // Never the password itself, see password.rs.
+    password_hash: Option<String>,
+    nudge_rate_limiter: PlayerRateLimiter,
+    ping_rate_limiter: PlayerRateLimiter,
+    // Locked separately so bots can observe state pushes made with a read lock.
+    bots: Mutex<Vec<Box<dyn PlayerAgent>>>,
 }
 
 // TODO Add the ability to pause and resume the game.
 // TODO If any player disconnects, pause the game.
 impl GameWrapper {
-    pub fn new(game_handle: GameHandle, game_options: GameOptions) -> GameWrapper {
-        let game = Game::new(game_handle, game_options);
+    pub fn new(
+        game_handle: GameHandle,
+        game_options: GameOptions,
+        host: Option<PlayerName>,
+        public: bool,
+        password_hash: Option<String>,
+    ) -> GameWrapper {
+        let mut game = Game::new(game_handle, game_options);
+        if let Some(host) = host {
+            game.game_state.host = host;
+        }
         GameWrapper {
             game,
-            actors: vec![],
+            actors: HashMap::new(),
+            chat_log: ChatLog::new(),
+            public,
+            password_hash,
+            nudge_rate_limiter: PlayerRateLimiter::new(
+                NUDGE_RATE_LIMIT_MESSAGES,
+                NUDGE_RATE_LIMIT_WINDOW_SECS,
+            ),
+            ping_rate_limiter: PlayerRateLimiter::new(
+                PING_RATE_LIMIT_MESSAGES,
+                PING_RATE_LIMIT_WINDOW_SECS,
+            ),
+            bots: Mutex::new(Vec::new()),
         }
     }
 
@@ -40,56 +108,323 @@ impl GameWrapper {
         self.game.game_created
     }
 
+    pub fn get_game_state(&self) -> &GameState {
+        &self.game.game_state
+    }
+
+    pub fn get_summary(&self) -> GameSummary {
+        let game_state = &self.game.game_state;
+        GameSummary {
+            handle: self.game.game_handle.0.clone(),
+            game_status: game_state.game_status,
+            host: game_state.host.0.clone(),
+            num_players: game_state.players.len(),
+            max_players: MAX_PLAYERS,
+            game_created: self.game.game_created,
+            public: self.public,
+            has_password: self.password_hash.is_some(),
+            enforce_silence: self.game.game_options.enforce_silence,
+            teleport_only_from_portal: self.game.game_options.teleport_only_from_portal,
+        }
+    }
+
     pub fn add_player(&mut self, name: String) -> Result<()> {
         self.game.add_player(name)
     }
 
-    pub fn add_actor(&mut self, actor: Addr<MyWs>) {
+    pub fn add_actor(&mut self, player_name: PlayerName, actor: Addr<MyWs>) {
+        self.drop_dead_actors();
+        // Catch the new connection up on what has been said so far.
+        for chat_message in self.chat_log.get_history() {
+            actor.do_send(InternalMessage::from_chat(chat_message));
+        }
+        self.set_presence(&player_name, Presence::Online);
+        // Only one connection per name gets updates, so close the one this
+        // replaces rather than leave it open and silent.
+        if let Some(replaced) = self.actors.insert(player_name, actor.clone()) {
+            if replaced != actor {
+                replaced.do_send(CloseConnection {
+                    reason: CloseReason::JoinedElsewhere,
+                });
+            }
+        }
+    }
+
+    /// Add the actor and push the state to everyone, so the new connection
+    /// gets it and the others see the player is here.
+    pub fn register_actor(&mut self, player_name: PlayerName, actor: Addr<MyWs>) {
+        self.add_actor(player_name, actor);
+        match self.push_state() {
+            Ok(_) => (),
+            Err(e) => error!(
+                "Failed to push state for {}: {:?}",
+                self.game.game_handle.0, e
+            ),
+        }
+    }
+
+    /// Returns whether the presence changed, see GameState::set_presence.
+    /// Only the actor registered for the player can change it, so an old
+    /// connection timing out doesn't make a reconnected player look away.
+    pub fn set_actor_presence(
+        &mut self,
+        player_name: &PlayerName,
+        actor: &Addr<MyWs>,
+        presence: Presence,
+    ) -> bool {
+        if self.actors.get(player_name) != Some(actor) {
+            return false;
+        }
+        self.set_presence(player_name, presence)
+    }
+
+    fn set_presence(&mut self, player_name: &PlayerName, presence: Presence) -> bool {
+        self.game.game_state.set_presence(player_name, presence)
+    }
+
+    /// Called when an actor stops. The actor is only removed if it is still the
+    /// one registered for the player, so a reconnect isn't clobbered by the old
+    /// connection going away. If the player was the host, the role migrates.
+    pub fn remove_actor(&mut self, player_name: &PlayerName, actor: &Addr<MyWs>) {
+        if self.actors.get(player_name) != Some(actor) {
+            return;
+        }
+        self.actors.remove(player_name);
+        self.set_presence(player_name, Presence::Offline);
         self.drop_dead_actors();
-        self.actors.push(actor);
+        if self.game.game_state.is_host(player_name) {
+            let connected_players: Vec<PlayerName> = self.actors.keys().cloned().collect();
+            self.game.migrate_host(&connected_players);
+        }
+    }
+
+    /// Close the connection for the given player, e.g. when they are kicked.
+    pub fn close_actor(&mut self, player_name: &PlayerName, reason: CloseReason) {
+        if let Some(a) = self.actors.remove(player_name) {
+            self.set_presence(player_name, Presence::Offline);
+            a.do_send(CloseConnection { reason });
+        }
+    }
+
+    /// Close every connection, e.g. when the game is reaped.
+    pub fn close_all_actors(&mut self, reason: CloseReason) {
+        for (_, a) in self.actors.drain() {
+            a.do_send(CloseConnection {
+                reason: reason.clone(),
+            });
+        }
     }
 
     pub fn drop_dead_actors(&mut self) {
-        for a in self.actors.iter() {
-            if !a.connected() {
-                warn!(
-                    "Dropping dead actor from {}: {:?}",
-                    self.game.game_handle.0, a
-                );
-            }
+        let dead: Vec<PlayerName> = self
+            .actors
+            .iter()
+            .filter(|(_, a)| !a.connected())
+            .map(|(n, _)| n.clone())
+            .collect();
+        for n in dead {
+            let a = self.actors.remove(&n).unwrap();
+            warn!(
+                "Dropping dead actor for {} from {}: {:?}",
+                n.0, self.game.game_handle.0, a
+            );
+            self.set_presence(&n, Presence::Offline);
         }
-        self.actors.retain(|a| a.connected());
     }
 
-    pub fn push_state(&self) -> Result<()> {
-        let game_state = self.game.get_game_state();
-        let internal_message = InternalMessage::from_game_state(game_state);
-        for a in self.actors.iter() {
+    fn broadcast(&self, internal_message: InternalMessage) {
+        for a in self.actors.values() {
             // TODO Consider using send instead.
             a.do_send(internal_message.clone());
         }
+    }
+
+    pub fn push_state(&self) -> Result<()> {
+        let game_state = self.game.get_game_state();
+        for bot in self.bots.lock().unwrap().iter_mut() {
+            bot.observe(&game_state);
+        }
+        self.broadcast(InternalMessage::from_game_state(game_state));
         Ok(())
     }
 
-    pub fn push_chat(&self, chat: String) -> Result<()> {
-        let internal_message = InternalMessage::from_chat(chat);
-        for a in self.actors.iter() {
-            // TODO Consider using send instead.
-            a.do_send(internal_message.clone());
+    /// Make sure there is exactly one agent for each bot player in the game.
+    fn sync_bots(&mut self) {
+        let game_state = &self.game.game_state;
+        let bots = self.bots.get_mut().unwrap();
+        bots.retain(|b| game_state.is_bot(b.name()));
+        for player in game_state.players.iter().filter(|p| p.is_bot) {
+            let name = PlayerName(player.name.clone());
+            if bots.iter().all(|b| b.name() != &name) {
+                let mut bot =
+                    SimpleBot::new(name, self.game.game_options.teleport_only_from_portal);
+                bot.observe(game_state);
+                bots.push(Box::new(bot));
+            }
         }
+    }
+
+    /// Give each bot a chance to do something. This is called periodically.
+    pub fn run_bots(&mut self) {
+        let num_bots = self.bots.get_mut().unwrap().len();
+        for i in 0..num_bots {
+            let (name, message) = {
+                let bot = &mut self.bots.get_mut().unwrap()[i];
+                (bot.name().clone(), bot.next_message())
+            };
+            let message = match message {
+                Some(message) => message,
+                None => continue,
+            };
+            match self.game.handle_message(message, &name) {
+                MoveValidity::Valid => match self.push_state() {
+                    Ok(_) => (),
+                    Err(e) => warn!("Failed to push state after bot {} moved: {:?}", name.0, e),
+                },
+                MoveValidity::Invalid(reason) => {
+                    debug!("Bot {} tried something invalid: {}", name.0, reason)
+                }
+            }
+        }
+    }
+
+    pub fn push_chat(&self, chat: ChatMessage) -> Result<()> {
+        self.broadcast(InternalMessage::from_chat(chat));
         Ok(())
     }
 
+    /// Nudges are events rather than game state, so they are just sent on
+    /// to everyone as they come in.
+    pub fn push_nudge(&mut self, nudge: Nudge, player_name: &PlayerName) -> MoveValidity {
+        let validity = self.game.validate_nudge(&nudge);
+        if validity.is_invalid() {
+            return validity;
+        }
+        if !self
+            .nudge_rate_limiter
+            .try_send(player_name, get_current_time_secs())
+        {
+            return MoveValidity::Invalid("You are nudging too often".to_string());
+        }
+        let nudge = Nudge {
+            sender: player_name.clone(),
+            ..nudge
+        };
+        self.broadcast(InternalMessage::from_nudge(nudge));
+        MoveValidity::Valid
+    }
+
+    /// Like nudges, pings are just sent on to everyone.
+    pub fn push_ping(&mut self, ping: Ping, player_name: &PlayerName) -> MoveValidity {
+        let validity = self.game.validate_ping(&ping);
+        if validity.is_invalid() {
+            return validity;
+        }
+        if !self
+            .ping_rate_limiter
+            .try_send(player_name, get_current_time_secs())
+        {
+            return MoveValidity::Invalid("You are pinging too often".to_string());
+        }
+        let ping = Ping {
+            sender: player_name.clone(),
+            ..ping
+        };
+        self.broadcast(InternalMessage::from_ping(ping));
+        MoveValidity::Valid
+    }
+
+    /// Hints are shared with the whole team, since they name an ability
+    /// rather than a player.
+    pub fn push_hint(&mut self, player_name: &PlayerName) -> MoveValidity {
+        match self.game.request_hint(player_name) {
+            Ok(hint) => {
+                self.broadcast(InternalMessage::from_hint(hint));
+                MoveValidity::Valid
+            }
+            Err(e) => MoveValidity::Invalid(e.to_string()),
+        }
+    }
+
+    /// Queries are answered only to the player who asked and don't change the
+    /// game. Returns None if the message isn't a query.
+    pub fn handle_query(&self, body: &Body, player_name: &PlayerName) -> Option<InternalMessage> {
+        match body {
+            Body::QueryMoves(qm) => {
+                let query_moves = match QueryMoves::from_proto(qm.clone()) {
+                    Ok(query_moves) => query_moves,
+                    Err(e) => return Some(InternalMessage::from_invalid_reason(e.to_string())),
+                };
+                Some(match self.game.query_moves(query_moves, player_name) {
+                    Ok(possible_moves) => InternalMessage::from_possible_moves(possible_moves),
+                    Err(e) => InternalMessage::from_invalid_reason(e.to_string()),
+                })
+            }
+            Body::PlanPath(pp) => {
+                let plan_path = match PlanPath::from_proto(pp.clone()) {
+                    Ok(plan_path) => plan_path,
+                    Err(e) => return Some(InternalMessage::from_invalid_reason(e.to_string())),
+                };
+                Some(match self.game.plan_path(plan_path, player_name) {
+                    Ok(path_plan) => InternalMessage::from_path_plan(path_plan),
+                    Err(e) => InternalMessage::from_invalid_reason(e.to_string()),
+                })
+            }
+            _ => None,
+        }
+    }
+
     pub fn handle_message(
         &mut self,
         message: MainMessage,
         player_name: &PlayerName,
     ) -> MoveValidity {
-        if let Body::Chat(c) = message.clone().body.unwrap() {
-            self.push_chat(c).unwrap();
-            return MoveValidity::Valid;
+        let body = message.clone().body.unwrap();
+        if let Body::Chat(c) = body {
+            let validity = self.game.validate_chat(&c);
+            if validity.is_invalid() {
+                return validity;
+            }
+            return match self.chat_log.add_message(player_name, c) {
+                Ok(chat_message) => {
+                    self.push_chat(chat_message).unwrap();
+                    MoveValidity::Valid
+                }
+                Err(e) => MoveValidity::Invalid(e.to_string()),
+            };
+        }
+        if let Body::Nudge(n) = body {
+            return match Nudge::from_proto(n) {
+                Ok(nudge) => self.push_nudge(nudge, player_name),
+                Err(e) => MoveValidity::Invalid(e.to_string()),
+            };
+        }
+        if let Body::Ping(p) = body {
+            return match Ping::from_proto(p) {
+                Ok(ping) => self.push_ping(ping, player_name),
+                Err(e) => MoveValidity::Invalid(e.to_string()),
+            };
+        }
+        if let Body::RequestHint(_) = body {
+            return self.push_hint(player_name);
         }
-        self.game.handle_message(message, &player_name)
+        let validity = self.game.handle_message(message, &player_name);
+        if validity.is_invalid() {
+            return validity;
+        }
+        self.sync_bots();
+        // Some messages also change who is connected.
+        match body {
+            Body::KickPlayer(kp) => self.close_actor(&PlayerName(kp.name), CloseReason::Kicked),
+            Body::LeaveGame(_) => self.close_actor(player_name, CloseReason::LeftGame),
+            Body::RenamePlayer(rp) => {
+                if let Some(a) = self.actors.remove(player_name) {
+                    self.actors.insert(PlayerName(rp.name), a);
+                }
+            }
+            _ => (),
+        }
+        validity
     }
 }
 
@@ -118,10 +453,10 @@ impl GameManager {
     pub fn new_game(
         &mut self,
         game_options: GameOptions,
-        handle: Option<String>,
+        create_options: CreateOptions,
     ) -> Result<GameHandle> {
         let in_use_handles: HashSet<String> = self.get_in_use_handles();
-        let handle = match handle {
+        let handle = match create_options.handle {
             Some(handle) => {
                 if in_use_handles.contains(&handle) {
                     return Err(anyhow!("Game handle {} already in use", handle));
@@ -142,6 +477,9 @@ impl GameManager {
         let game_wrapper = Arc::new(RwLock::new(GameWrapper::new(
             game_handle.clone(),
             game_options,
+            create_options.host,
+            create_options.public,
+            create_options.password_hash,
         )));
 
         self.games.insert(game_handle.clone(), game_wrapper);
@@ -151,15 +489,62 @@ impl GameManager {
         Ok(game_handle)
     }
 
-    pub fn join_game(&mut self, join_options: JoinOptions) -> Result<Arc<RwLock<GameWrapper>>> {
+    pub fn get_game(&self, handle: &GameHandle) -> Result<Arc<RwLock<GameWrapper>>> {
+        match self.games.get(handle) {
+            Some(game_wrapper) => Ok(game_wrapper.clone()),
+            None => Err(anyhow!(format!(
+                "Game with handle \"{}\" does not exist",
+                handle.0
+            ))),
+        }
+    }
+
+    /// Summaries of every game, sorted by handle.
+    pub fn get_game_summaries(&self) -> Vec<GameSummary> {
+        let mut summaries: Vec<GameSummary> = self
+            .games
+            .values()
+            .map(|game_wrapper| game_wrapper.read().unwrap().get_summary())
+            .collect();
+        summaries.sort_by(|a, b| a.handle.cmp(&b.handle));
+        summaries
+    }
+
+    /// Summaries of the games listed in the lobby, sorted by handle. Other
+    /// games can only be found by being told their handle.
+    pub fn get_public_games(&self) -> Vec<GameSummary> {
+        self.get_game_summaries()
+            .into_iter()
+            .filter(|s| s.public)
+            .collect()
+    }
+
+    /// The game's password hash, if it has a password. Check it against what
+    /// the player gave, see password::check_password, before letting them in.
+    pub fn get_password_hash(&self, handle: &GameHandle) -> Option<String> {
+        let game_wrapper = self.games.get(handle)?;
+        let password_hash = game_wrapper.read().unwrap().password_hash.clone();
+        password_hash
+    }
+
+    /// Public games that haven't started yet, for people looking for a game.
+    pub fn get_lobby_games(&self) -> Vec<GameSummary> {
+        self.get_public_games()
+            .into_iter()
+            .filter(|s| s.game_status == GameStatus::Staging)
+            .collect()
+    }
+
+    /// Adds the player to the game, unless they're already in it. The reason
+    /// joining failed is sent to the client when closing its websocket. The
+    /// password, if the game has one, must have been checked already.
+    pub fn join_game(
+        &mut self,
+        join_options: JoinOptions,
+    ) -> std::result::Result<Arc<RwLock<GameWrapper>>, CloseReason> {
         let game_wrapper = match self.games.get_mut(&join_options.handle) {
             Some(game_wrapper) => game_wrapper,
-            None => {
-                return Err(anyhow!(format!(
-                    "Game with handle \"{}\" does not exist",
-                    join_options.handle.0
-                )))
-            }
+            None => return Err(CloseReason::GameNotFound(join_options.handle.0)),
         };
 
         {
@@ -171,7 +556,26 @@ impl GameManager {
                 join_prefix_str = "RE-";
             } else {
                 join_prefix_str = "";
-                game_wrapper.add_player(player_name)?;
+                let name_validity = game_wrapper
+                    .game
+                    .game_state
+                    .validate_player_name(&PlayerName(player_name.clone()));
+                if let MoveValidity::Invalid(reason) = name_validity {
+                    return Err(CloseReason::InvalidName(reason));
+                }
+                let game_state = &game_wrapper.game.game_state;
+                if !join_options.spectate {
+                    if game_state.game_status != GameStatus::Staging {
+                        return Err(CloseReason::GameAlreadyStarted);
+                    }
+                    if game_state.is_full() {
+                        return Err(CloseReason::GameFull);
+                    }
+                }
+                // Spectators aren't added, see GameState::add_player.
+                if let Err(e) = game_wrapper.add_player(player_name) {
+                    return Err(CloseReason::InvalidName(e.to_string()));
+                }
             }
             info!(
                 "Player {} {}joined game {}",
@@ -183,18 +587,4 @@ impl GameManager {
 
         Ok(game_wrapper.clone())
     }
-
-    pub fn register_actor(&mut self, game_handle: GameHandle, actor: Addr<MyWs>) {
-        let game_wrapper = match self.games.get_mut(&game_handle) {
-            Some(game_wrapper) => game_wrapper,
-            None => panic!("Game we just made doesn't exist"),
-        };
-        let mut game_wrapper = game_wrapper.write().unwrap();
-        game_wrapper.add_actor(actor);
-        // Push initial state / update other clients that there is a new player.
-        match game_wrapper.push_state() {
-            Ok(_) => (),
-            Err(e) => error!("Failed to push state for {}: {:?}", game_handle.0, e),
-        }
-    }
 }
@@ -0,0 +1,74 @@
+// Game passwords, so only people who've been told it can join a game, rather
+// than anyone who guesses a handle. Only a salted PBKDF2 hash is kept, in the
+// form "pbkdf2-sha256$<rounds>$<salt>$<hash>", with the salt and hash base64.
+// Hashing and checking are slow on purpose, so don't do them holding a lock.
+
+use anyhow::{anyhow, Result};
+use hmac::Hmac;
+use log::error;
+use pbkdf2::pbkdf2;
+use rand::RngCore;
+use sha2::Sha256;
+
+const SCHEME: &str = "pbkdf2-sha256";
+pub const PBKDF2_ROUNDS: u32 = 100_000;
+const SALT_BYTES: usize = 16;
+const HASH_BYTES: usize = 32;
+
+fn derive(password: &str, salt: &[u8], rounds: u32) -> [u8; HASH_BYTES] {
+    let mut hash = [0u8; HASH_BYTES];
+    pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, rounds, &mut hash);
+    hash
+}
+
+pub fn hash_password(password: &str) -> String {
+    let mut salt = [0u8; SALT_BYTES];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let hash = derive(password, &salt, PBKDF2_ROUNDS);
+    format!(
+        "{}${}${}${}",
+        SCHEME,
+        PBKDF2_ROUNDS,
+        base64::encode(salt),
+        base64::encode(hash)
+    )
+}
+
+fn parse_hash(password_hash: &str) -> Result<(u32, Vec<u8>, Vec<u8>)> {
+    let parts: Vec<&str> = password_hash.split('$').collect();
+    if parts.len() != 4 || parts[0] != SCHEME {
+        return Err(anyhow!("Unknown password hash format"));
+    }
+    let rounds = parts[1].parse()?;
+    let salt = base64::decode(parts[2])?;
+    let hash = base64::decode(parts[3])?;
+    Ok((rounds, salt, hash))
+}
+
+/// Whether the password matches a hash made by hash_password. Compares in
+/// constant time so how long it takes doesn't give the hash away.
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    let (rounds, salt, hash) = match parse_hash(password_hash) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            error!("Failed to parse password hash: {}", e);
+            return false;
+        }
+    };
+    let derived = derive(password, &salt, rounds);
+    derived.len() == hash.len()
+        && derived
+            .iter()
+            .zip(hash.iter())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// Games without a password, i.e. without a hash, let anyone in.
+pub fn check_password(password_hash: Option<&str>, password: Option<&str>) -> bool {
+    match (password_hash, password) {
+        (None, _) => true,
+        (Some(password_hash), Some(password)) => verify_password(password, password_hash),
+        (Some(_), None) => false,
+    }
+}
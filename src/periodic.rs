@@ -1,15 +1,17 @@
 use log::info;
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use std::thread;
 use std::time::Duration;
 
+use crate::close_reason::CloseReason;
 use crate::game::GameHandle;
 use crate::manager::{GameManagerWrapper, GameWrapper, TEST_HANDLE};
 use crate::utils::get_current_time_secs;
 
 const REAP_DURATION: u64 = 3600; // 1hr from creation, games are reaped
 const REAP_INTERVAL: u64 = 600; // 10m between reap calls
+const BOT_INTERVAL_MILLIS: u64 = 1500; // Bots act at most this often
 
 pub fn reaper(game_manager_wrapper: &GameManagerWrapper) {
     thread::sleep(Duration::from_secs(REAP_INTERVAL));
@@ -26,6 +28,10 @@ pub fn reaper(game_manager_wrapper: &GameManagerWrapper) {
         if handle == &test_handle || (creation_time + REAP_DURATION) > get_current_time_secs() {
             unreaped_games.insert(handle.clone(), game_wrapper.clone());
         } else {
+            game_wrapper
+                .write()
+                .unwrap()
+                .close_all_actors(CloseReason::GameReaped);
             reaped_games.push(handle.clone());
         }
     }
@@ -37,3 +43,20 @@ pub fn reaper(game_manager_wrapper: &GameManagerWrapper) {
 
     game_manager.games = unreaped_games;
 }
+
+pub fn bot_runner(game_manager_wrapper: &GameManagerWrapper) {
+    thread::sleep(Duration::from_millis(BOT_INTERVAL_MILLIS));
+
+    // Don't hold the manager lock while the bots think.
+    let game_wrappers: Vec<Arc<RwLock<GameWrapper>>> = game_manager_wrapper
+        .game_manager
+        .read()
+        .unwrap()
+        .games
+        .values()
+        .cloned()
+        .collect();
+    for game_wrapper in game_wrappers {
+        game_wrapper.write().unwrap().run_bots();
+    }
+}
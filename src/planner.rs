@@ -0,0 +1,146 @@
+// Plans how to get a heister to a square using the abilities of the whole team.
+
+use crate::game_state::GameState;
+use crate::types::{
+    get_wall_color, Ability, Heister, HeisterColor, MapPosition, PlanStep, PlayerName,
+};
+
+use std::collections::{HashMap, VecDeque};
+
+/// Names of the players who have the given ability.
+pub fn players_with_ability(game_state: &GameState, ability: Ability) -> Vec<PlayerName> {
+    game_state
+        .players
+        .iter()
+        .filter(|p| p.abilities.contains(&ability))
+        .map(|p| PlayerName(p.name.clone()))
+        .collect()
+}
+
+/// Every ability at least one player in the game has.
+pub fn team_abilities(game_state: &GameState) -> Vec<Ability> {
+    let mut abilities = Vec::new();
+    for player in &game_state.players {
+        for ability in &player.abilities {
+            if !abilities.contains(ability) {
+                abilities.push(*ability);
+            }
+        }
+    }
+    abilities
+}
+
+/// Finds the fewest moves it takes to get the heister to the target, where a
+/// move is a slide in one direction, an escalator ride or a teleport. The other
+/// heisters are assumed to stay where they are. Returns None if the target
+/// can't be reached, and no steps if the heister is already there.
+pub fn plan_path(
+    game_state: &GameState,
+    heister_color: HeisterColor,
+    target: &MapPosition,
+    teleport_only_from_portal: bool,
+) -> Option<Vec<PlanStep>> {
+    let start = match game_state.get_heister_from_vec(heister_color) {
+        Some(h) if !h.has_escaped => h.map_position,
+        _ => return None,
+    };
+    let grid = game_state.get_absolute_grid();
+    let abilities = team_abilities(game_state);
+
+    // Breadth first search, remembering how we first got to each square.
+    let mut came_from: HashMap<MapPosition, (MapPosition, Ability)> = HashMap::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    while let Some(pos) = queue.pop_front() {
+        if &pos == target {
+            break;
+        }
+        for possible_move in game_state.get_possible_moves_from(
+            &grid,
+            heister_color,
+            pos,
+            &abilities,
+            teleport_only_from_portal,
+        ) {
+            let next = possible_move.position;
+            if next == start || came_from.contains_key(&next) {
+                continue;
+            }
+            came_from.insert(next, (pos, possible_move.ability));
+            queue.push_back(next);
+        }
+    }
+
+    if target != &start && !came_from.contains_key(target) {
+        return None;
+    }
+
+    let mut steps = Vec::new();
+    let mut pos = *target;
+    while pos != start {
+        let (prev, ability) = came_from[&pos];
+        steps.push(PlanStep {
+            position: pos,
+            ability,
+            players: players_with_ability(game_state, ability),
+        });
+        pos = prev;
+    }
+    steps.reverse();
+    Some(steps)
+}
+
+/// Where the heister should be heading next, if anywhere: its item, then any
+/// exit once all the items are taken. If its item hasn't been found yet, any
+/// unexplored door of its color.
+pub fn heister_goals(game_state: &GameState, heister: &Heister) -> Vec<MapPosition> {
+    let grid = game_state.get_absolute_grid();
+    let color = heister.heister_color;
+    if game_state.all_items_taken {
+        return grid
+            .iter()
+            .filter(|(_, square)| square.is_escape())
+            .map(|(pos, _)| *pos)
+            .collect();
+    }
+    let items: Vec<MapPosition> = grid
+        .iter()
+        .filter(|(_, square)| square.is_item() && square.color() == Some(color))
+        .map(|(pos, _)| *pos)
+        .collect();
+    if items.contains(&heister.map_position) {
+        // Wait here for the others.
+        return vec![];
+    }
+    if !items.is_empty() {
+        return items;
+    }
+    grid.iter()
+        .filter(|(_, square)| match square.get_door_wall() {
+            Some(door) => get_wall_color(door) == Some(color),
+            None => false,
+        })
+        .map(|(pos, _)| *pos)
+        .filter(|pos| pos != &heister.map_position)
+        .collect()
+}
+
+/// The shortest plan to any of the heister's goals, if it has anywhere to go.
+pub fn plan_to_nearest_goal(
+    game_state: &GameState,
+    heister: &Heister,
+    teleport_only_from_portal: bool,
+) -> Option<Vec<PlanStep>> {
+    heister_goals(game_state, heister)
+        .iter()
+        .filter_map(|goal| {
+            plan_path(
+                game_state,
+                heister.heister_color,
+                goal,
+                teleport_only_from_portal,
+            )
+        })
+        .filter(|steps| !steps.is_empty())
+        .min_by_key(|steps| steps.len())
+}
@@ -0,0 +1,303 @@
+// Limits how quickly each connection can send messages, so a buggy client
+// spamming drags can't hold the game's write lock. Every message takes a
+// token from the connection's bucket, and some message types also take one
+// from a bucket of their own. Messages over the limit are rejected, and a
+// connection that keeps going over it is disconnected. Wrong game passwords
+// are limited per game and address too, see PasswordAttemptLimiter.
+
+use crate::game::GameHandle;
+use crate::types::main_message::Body;
+
+use std::collections::HashMap;
+use std::env;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+// Once we're keeping track of this many games and addresses, forget the ones
+// that haven't got a password wrong in a while.
+const MAX_TRACKED_ADDRESSES: usize = 10_000;
+
+/// A bucket holds up to `capacity` tokens and gains `refill_per_sec` tokens
+/// a second, so clients can send bursts as long as they average out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BucketConfig {
+    pub capacity: f64,
+    pub refill_per_sec: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimitConfig {
+    // Every message from the connection.
+    pub connection: BucketConfig,
+    // Message types with limits of their own, see message_type.
+    pub per_type: HashMap<&'static str, BucketConfig>,
+    // Each rejected message takes a token from this, and the connection is
+    // closed when it runs out.
+    pub violations: BucketConfig,
+    // Each wrong game password takes a token from the address's bucket.
+    pub password_attempts: BucketConfig,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        let mut per_type = HashMap::new();
+        // Dragging a heister sends these continuously.
+        for message_type in &["QueryMoves", "PlanPath"] {
+            per_type.insert(
+                *message_type,
+                BucketConfig {
+                    capacity: 20.0,
+                    refill_per_sec: 10.0,
+                },
+            );
+        }
+        for message_type in &["Move", "Command"] {
+            per_type.insert(
+                *message_type,
+                BucketConfig {
+                    capacity: 10.0,
+                    refill_per_sec: 5.0,
+                },
+            );
+        }
+        per_type.insert(
+            "PlaceTile",
+            BucketConfig {
+                capacity: 5.0,
+                refill_per_sec: 2.0,
+            },
+        );
+        RateLimitConfig {
+            connection: BucketConfig {
+                capacity: 40.0,
+                refill_per_sec: 20.0,
+            },
+            per_type,
+            violations: BucketConfig {
+                capacity: 50.0,
+                refill_per_sec: 1.0,
+            },
+            password_attempts: BucketConfig {
+                capacity: 10.0,
+                refill_per_sec: 0.1,
+            },
+        }
+    }
+}
+
+impl BucketConfig {
+    /// Override the bucket with <prefix>_BURST and <prefix>_PER_SEC if
+    /// they're set.
+    fn override_from_env(&mut self, prefix: &str) {
+        if let Some(capacity) = env::var(format!("{}_BURST", prefix))
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            self.capacity = capacity;
+        }
+        if let Some(refill_per_sec) = env::var(format!("{}_PER_SEC", prefix))
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            self.refill_per_sec = refill_per_sec;
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// The defaults, with each bucket overridden from the environment if set:
+    /// THT_RATE_LIMIT for the connection, THT_RATE_LIMIT_VIOLATIONS for
+    /// violations, THT_RATE_LIMIT_PASSWORD for wrong passwords and e.g.
+    /// THT_RATE_LIMIT_PLACETILE for PlaceTile messages. Each takes a _BURST
+    /// and a _PER_SEC suffix.
+    pub fn from_env() -> Self {
+        let mut config = RateLimitConfig::default();
+        config.connection.override_from_env("THT_RATE_LIMIT");
+        config
+            .violations
+            .override_from_env("THT_RATE_LIMIT_VIOLATIONS");
+        config
+            .password_attempts
+            .override_from_env("THT_RATE_LIMIT_PASSWORD");
+        for (message_type, bucket_config) in config.per_type.iter_mut() {
+            bucket_config
+                .override_from_env(&format!("THT_RATE_LIMIT_{}", message_type.to_uppercase()));
+        }
+        config
+    }
+}
+
+#[derive(Debug)]
+pub struct TokenBucket {
+    config: BucketConfig,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Buckets start full.
+    pub fn new(config: BucketConfig, now: Instant) -> Self {
+        TokenBucket {
+            config,
+            tokens: config.capacity,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.config.refill_per_sec)
+            .min(self.config.capacity);
+        self.last_refill = now;
+    }
+
+    /// Take a token at time `now` if there is one. Returns whether there was.
+    pub fn try_take(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    /// Put back a token taken with try_take.
+    pub fn give_back(&mut self) {
+        self.tokens = (self.tokens + 1.0).min(self.config.capacity);
+    }
+
+    fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.config.capacity
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RateLimitDecision {
+    Allowed,
+    // The message should be rejected with this reason.
+    Limited(String),
+    // The connection has gone over its limits too often.
+    Disconnect,
+}
+
+#[derive(Debug)]
+pub struct ConnectionRateLimiter {
+    config: RateLimitConfig,
+    connection: TokenBucket,
+    per_type: HashMap<&'static str, TokenBucket>,
+    violations: TokenBucket,
+}
+
+impl ConnectionRateLimiter {
+    pub fn new(config: RateLimitConfig, now: Instant) -> Self {
+        ConnectionRateLimiter {
+            connection: TokenBucket::new(config.connection, now),
+            per_type: HashMap::new(),
+            violations: TokenBucket::new(config.violations, now),
+            config,
+        }
+    }
+
+    /// Check a message of the given type, see message_type, received at `now`.
+    pub fn check(&mut self, message_type: &'static str, now: Instant) -> RateLimitDecision {
+        if !self.connection.try_take(now) {
+            return self.violation("You are sending messages too quickly".to_string(), now);
+        }
+        if let Some(bucket_config) = self.config.per_type.get(message_type) {
+            let bucket = self
+                .per_type
+                .entry(message_type)
+                .or_insert_with(|| TokenBucket::new(*bucket_config, now));
+            if !bucket.try_take(now) {
+                return self.violation(
+                    format!("You are sending {} messages too quickly", message_type),
+                    now,
+                );
+            }
+        }
+        RateLimitDecision::Allowed
+    }
+
+    /// Record a message that was rejected for some other reason, e.g. being
+    /// too big, so clients that keep sending them are disconnected too.
+    pub fn violation(&mut self, reason: String, now: Instant) -> RateLimitDecision {
+        match self.violations.try_take(now) {
+            true => RateLimitDecision::Limited(reason),
+            false => RateLimitDecision::Disconnect,
+        }
+    }
+}
+
+/// Limits how many wrong passwords each address can send for each game, so
+/// they can't be guessed. Keying on the game too means that when every client
+/// comes through the same proxy, guessing at one game doesn't lock everyone
+/// out of the rest. Every attempt takes a token before it's checked, and right
+/// ones get theirs back, so a flood of guesses can't tie up the server either.
+#[derive(Debug)]
+pub struct PasswordAttemptLimiter {
+    config: BucketConfig,
+    buckets: Mutex<HashMap<(GameHandle, IpAddr), TokenBucket>>,
+}
+
+impl PasswordAttemptLimiter {
+    pub fn new(config: BucketConfig) -> Self {
+        PasswordAttemptLimiter {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the address may try a password for the game at `now`.
+    pub fn try_attempt(&self, handle: &GameHandle, address: IpAddr, now: Instant) -> bool {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_TRACKED_ADDRESSES {
+            buckets.retain(|_, bucket| !bucket.is_full(now));
+        }
+        let config = self.config;
+        buckets
+            .entry((handle.clone(), address))
+            .or_insert_with(|| TokenBucket::new(config, now))
+            .try_take(now)
+    }
+
+    /// The password the address tried for the game was right, so it doesn't
+    /// count.
+    pub fn succeeded(&self, handle: &GameHandle, address: IpAddr) {
+        let key = (handle.clone(), address);
+        if let Some(bucket) = self.buckets.lock().unwrap().get_mut(&key) {
+            bucket.give_back();
+        }
+    }
+}
+
+/// The name of the message type, for per type limits and logging.
+pub fn message_type(body: &Body) -> &'static str {
+    match body {
+        Body::GameState(_) => "GameState",
+        Body::InvalidRequest(_) => "InvalidRequest",
+        Body::StartGame(_) => "StartGame",
+        Body::Move(_) => "Move",
+        Body::PlaceTile(_) => "PlaceTile",
+        Body::Chat(_) => "Chat",
+        Body::KickPlayer(_) => "KickPlayer",
+        Body::TransferHost(_) => "TransferHost",
+        Body::LeaveGame(_) => "LeaveGame",
+        Body::RenamePlayer(_) => "RenamePlayer",
+        Body::ChatMessage(_) => "ChatMessage",
+        Body::Nudge(_) => "Nudge",
+        Body::Ping(_) => "Ping",
+        Body::QueryMoves(_) => "QueryMoves",
+        Body::PossibleMoves(_) => "PossibleMoves",
+        Body::PlanPath(_) => "PlanPath",
+        Body::PathPlan(_) => "PathPlan",
+        Body::AddBot(_) => "AddBot",
+        Body::RemoveBot(_) => "RemoveBot",
+        Body::RequestHint(_) => "RequestHint",
+        Body::Hint(_) => "Hint",
+        Body::Command(_) => "Command",
+        Body::Hello(_) => "Hello",
+        Body::ServerHello(_) => "ServerHello",
+    }
+}
@@ -0,0 +1,244 @@
+// Builds games from a text description, so rule tests can start from any
+// board without moving heisters there one step at a time. For example:
+//
+//   tile 1a at 0 0
+//   tile 5 at 1 -4 rotated 2
+//   player alice: MoveNorth MoveEast RevealTiles
+//   player bob: MoveSouth MoveWest Teleport UseEscalator
+//   board:
+//   ._.._..O.._.
+//   |*        @|
+//   ...
+//
+// The board is drawn the same way as GameState::render_ascii. Heister letters
+// say where the heisters are, * marks a timer that has been used, and an
+// "Escaped:" line lists heisters that have already left. The rest of the
+// board has to match the tiles, which catches typos in either.
+//
+// If no players are given, there is a single player called "player" with
+// every ability.
+
+use crate::game::{Game, GameHandle, GameOptions, MoveValidity};
+use crate::load_map::{load_tiles_from_json, tile_1a};
+use crate::types::main_message::Body;
+use crate::types::{
+    get_heister_letter, Ability, Command, Internal, MainMessage, MapPosition, PlayerName,
+    SquareType, Tile, ESCAPED, HEISTER_COLORS,
+};
+
+use anyhow::{anyhow, Result};
+
+pub const DEFAULT_PLAYER_NAME: &str = "player";
+
+const BOARD_PREFIX: &str = "board:";
+const ESCAPED_PREFIX: &str = "Escaped:";
+
+/// Strips the indentation shared by every non-empty line, along with blank
+/// lines at the start and end and trailing whitespace on every line.
+pub fn dedent(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().map(|l| l.trim_end()).collect();
+    let indent = lines
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let lines: Vec<&str> = lines
+        .iter()
+        .map(|l| match l.is_empty() {
+            true => *l,
+            false => &l[indent..],
+        })
+        .collect();
+    let first = lines.iter().position(|l| !l.is_empty()).unwrap_or(0);
+    let last = lines
+        .iter()
+        .rposition(|l| !l.is_empty())
+        .map_or(0, |i| i + 1);
+    lines[first..last.max(first)].join("\n")
+}
+
+fn find_tile(name: &str) -> Result<Tile> {
+    let mut tiles = load_tiles_from_json();
+    tiles.push(tile_1a());
+    match tiles.into_iter().find(|t| t.name == name) {
+        Some(tile) => Ok(tile),
+        None => Err(anyhow!("Unknown tile {}", name)),
+    }
+}
+
+fn parse_i32(word: Option<&&str>) -> Result<i32> {
+    match word.map(|w| w.parse::<i32>()) {
+        Some(Ok(n)) => Ok(n),
+        _ => Err(anyhow!("Expected a number, got {:?}", word)),
+    }
+}
+
+/// "tile <name> at <x> <y> [rotated <n>]"
+fn parse_tile(words: &[&str]) -> Result<Tile> {
+    if words.len() < 4 || words[1] != "at" {
+        return Err(anyhow!("Expected \"tile <name> at <x> <y> [rotated <n>]\""));
+    }
+    let tile = find_tile(words[0])?;
+    let position = MapPosition {
+        x: parse_i32(words.get(2))?,
+        y: parse_i32(words.get(3))?,
+    };
+    let num_rotations = match words.get(4) {
+        Some(&"rotated") => parse_i32(words.get(5))? as u32 % 4,
+        Some(word) => return Err(anyhow!("Unexpected \"{}\" after tile", word)),
+        None => 0,
+    };
+    let mut m = tile.to_matrix();
+    for _ in 0..num_rotations {
+        m = Tile::rotate_matrix_clockwise(&m);
+    }
+    Ok(Tile::from_matrix(m, tile.name, position, num_rotations))
+}
+
+fn parse_ability(word: &str) -> Result<Ability> {
+    (0..)
+        .map(Ability::from_i32)
+        .take_while(|a| a.is_some())
+        .flatten()
+        .find(|a| format!("{:?}", a) == word)
+        .ok_or_else(|| anyhow!("Unknown ability {}", word))
+}
+
+/// "player <name>: <ability> <ability> ..."
+fn parse_player(rest: &str) -> Result<(String, Vec<Ability>)> {
+    let mut parts = rest.splitn(2, ':');
+    let name = parts.next().unwrap().trim().to_string();
+    let abilities = match parts.next() {
+        Some(abilities) => abilities
+            .split_whitespace()
+            .map(parse_ability)
+            .collect::<Result<Vec<Ability>>>()?,
+        None => return Err(anyhow!("Expected \"player <name>: <abilities>\"")),
+    };
+    Ok((name, abilities))
+}
+
+/// Builds a started game from a scenario, see the top of this file.
+pub fn build_game(scenario: &str) -> Result<Game> {
+    let scenario = dedent(scenario);
+    let mut tiles = Vec::new();
+    let mut players = Vec::new();
+    let mut board_lines: Vec<&str> = Vec::new();
+    let mut in_board = false;
+    for line in scenario.lines() {
+        if in_board {
+            board_lines.push(line);
+            continue;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.first() {
+            None => continue,
+            Some(&"tile") => tiles.push(parse_tile(&words[1..])?),
+            Some(&"player") => players.push(parse_player(line.trim()["player".len()..].trim())?),
+            Some(&BOARD_PREFIX) => in_board = true,
+            Some(word) => return Err(anyhow!("Unexpected \"{}\" in scenario", word)),
+        }
+    }
+    if tiles.is_empty() {
+        return Err(anyhow!("A scenario needs at least one tile"));
+    }
+    if !in_board {
+        return Err(anyhow!("A scenario needs a board"));
+    }
+
+    let mut game = Game::new(
+        GameHandle("scenario".to_string()),
+        GameOptions {
+            shuffle_tiles: false,
+            ..GameOptions::default()
+        },
+    );
+    if players.is_empty() {
+        game.add_player(DEFAULT_PLAYER_NAME.to_string())?;
+        game.start_game();
+    } else {
+        for (name, _) in &players {
+            game.add_player(name.clone())?;
+        }
+        game.start_game();
+        for (player, (_, abilities)) in game.game_state.players.iter_mut().zip(players) {
+            player.abilities = abilities;
+        }
+    }
+
+    // Read the heisters and used timers off the board.
+    let board = dedent(&board_lines.join("\n"));
+    let (grid_lines, escaped) = match board.find(ESCAPED_PREFIX) {
+        Some(i) => (&board[..i], board[i + ESCAPED_PREFIX.len()..].trim()),
+        None => (board.as_str(), ""),
+    };
+    let rows: Vec<Vec<char>> = grid_lines.lines().map(|l| l.chars().collect()).collect();
+    let min_x = tiles.iter().map(|t| t.position.x).min().unwrap();
+    let min_y = tiles.iter().map(|t| t.position.y).min().unwrap();
+    let mut heister_positions = Vec::new();
+    for tile in tiles.iter_mut() {
+        for (i, square) in tile.squares.iter_mut().enumerate() {
+            let x = tile.position.x + (i % 4) as i32;
+            let y = tile.position.y + (i / 4) as i32;
+            let row = ((y - min_y) * 3 + 1) as usize;
+            let col = ((x - min_x) * 3 + 1) as usize;
+            let c = rows.get(row).and_then(|r| r.get(col)).copied();
+            if c == Some('*') && square.square_type == SquareType::TimerFlip {
+                square.square_type = SquareType::TimerFlipUsed;
+            }
+            for heister_color in HEISTER_COLORS.iter() {
+                if c == Some(get_heister_letter(**heister_color)) {
+                    heister_positions.push((**heister_color, MapPosition { x, y }));
+                }
+            }
+        }
+    }
+    for heister in game.game_state.heisters.iter_mut() {
+        let color = heister.heister_color;
+        let letter = get_heister_letter(color).to_string();
+        let position = heister_positions.iter().find(|(c, _)| c == &color);
+        match (position, escaped.split_whitespace().any(|l| l == letter)) {
+            (Some((_, position)), false) => heister.map_position = *position,
+            (None, true) => {
+                heister.map_position = *ESCAPED;
+                heister.has_escaped = true;
+            }
+            _ => return Err(anyhow!("{:?} must be on the board or escaped", color)),
+        }
+    }
+
+    game.set_tiles(tiles);
+    let rendered = dedent(&game.game_state.render_ascii());
+    if rendered != board {
+        return Err(anyhow!(
+            "The board doesn't match the tiles, expected:\n{}\ngot:\n{}",
+            rendered,
+            board
+        ));
+    }
+    Ok(game)
+}
+
+/// Panics with both boards if the game's board isn't the expected one.
+pub fn assert_board(game: &Game, expected: &str) {
+    let actual = dedent(&game.game_state.render_ascii());
+    let expected = dedent(expected);
+    assert!(
+        actual == expected,
+        "Board doesn't match, expected:\n{}\ngot:\n{}",
+        expected,
+        actual
+    );
+}
+
+/// Sends a text command (see command.rs) as the given player.
+pub fn run_command(game: &mut Game, player_name: &str, text: &str) -> MoveValidity {
+    let command = Command {
+        text: text.to_string(),
+    };
+    let message = MainMessage {
+        body: Some(Body::Command(command.to_proto())),
+    };
+    game.handle_message(message, &PlayerName(player_name.to_string()))
+}
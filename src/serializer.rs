@@ -1,11 +1,92 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use prost::Message as ProstMessage;
 
 use crate::game_state::GameState;
 use crate::types::main_message::Body;
-use crate::types::{Internal, InvalidRequest, MainMessage};
+use crate::types::{
+    ChatMessage, Hint, Internal, InvalidRequest, MainMessage, Nudge, PathPlan, Ping, PossibleMoves,
+    ServerHello, TryInternal,
+};
 use actix::Message as ActixMessage;
 
+/// Bump this whenever types.proto changes in a way old clients can't handle,
+/// and MIN_PROTOCOL_VERSION too if the server stops understanding them.
+pub const PROTOCOL_VERSION: u32 = 1;
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Optional features, so clients can hide what the server doesn't support.
+pub const CAPABILITIES: [&str; 5] = ["json", "commands", "chat", "hints", "path_planning"];
+
+/// Checks a client speaks a protocol version this server understands.
+pub fn check_protocol_version(protocol_version: u32) -> Result<()> {
+    match (MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&protocol_version) {
+        true => Ok(()),
+        false => Err(anyhow!(
+            "Client speaks protocol version {} but the server needs {} to {}, try reloading the page",
+            protocol_version,
+            MIN_PROTOCOL_VERSION,
+            PROTOCOL_VERSION
+        )),
+    }
+}
+
+/// The biggest message we'll decode. The game state for a finished game is
+/// well under this, even as JSON.
+pub const MAX_FRAME_BYTES: usize = 32 * 1024;
+
+fn check_frame_size(len: usize) -> Result<()> {
+    match len > MAX_FRAME_BYTES {
+        true => Err(anyhow!(
+            "Message is {} bytes, the most allowed is {}",
+            len,
+            MAX_FRAME_BYTES
+        )),
+        false => Ok(()),
+    }
+}
+
+pub const PROTO_SUBPROTOCOL: &str = "tht.proto";
+pub const JSON_SUBPROTOCOL: &str = "tht.json";
+pub const SUBPROTOCOLS: [&str; 2] = [PROTO_SUBPROTOCOL, JSON_SUBPROTOCOL];
+
+/// How messages are encoded on a websocket, chosen by the subprotocol the
+/// client asks for. Protobuf goes in binary frames and JSON in text frames.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WireFormat {
+    Proto,
+    Json,
+}
+
+impl WireFormat {
+    pub fn from_subprotocol(subprotocol: &str) -> Option<WireFormat> {
+        match subprotocol {
+            PROTO_SUBPROTOCOL => Some(WireFormat::Proto),
+            JSON_SUBPROTOCOL => Some(WireFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Picks the first subprotocol we support from the comma separated list
+    /// in a Sec-WebSocket-Protocol header. Clients that don't ask for one get
+    /// protobuf, which is what the browser client has always spoken.
+    pub fn from_protocols_header(header: Option<&str>) -> WireFormat {
+        header
+            .and_then(|h| {
+                h.split(',')
+                    .map(|p| p.trim())
+                    .find_map(WireFormat::from_subprotocol)
+            })
+            .unwrap_or(WireFormat::Proto)
+    }
+
+    pub fn subprotocol(&self) -> &'static str {
+        match self {
+            WireFormat::Proto => PROTO_SUBPROTOCOL,
+            WireFormat::Json => JSON_SUBPROTOCOL,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct InternalMessage {
     pub main_message: MainMessage,
@@ -20,6 +101,7 @@ impl InternalMessage {
     }
 
     pub fn from_bytes(wire_message: &[u8]) -> Result<InternalMessage> {
+        check_frame_size(wire_message.len())?;
         let main_message = match MainMessage::decode(wire_message) {
             Ok(main_message) => main_message,
             Err(e) => return Err(anyhow::Error::new(e)),
@@ -27,6 +109,16 @@ impl InternalMessage {
         Ok(InternalMessage { main_message })
     }
 
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.main_message).unwrap()
+    }
+
+    pub fn from_json(wire_message: &str) -> Result<InternalMessage> {
+        check_frame_size(wire_message.len())?;
+        let main_message = serde_json::from_str(wire_message)?;
+        Ok(InternalMessage { main_message })
+    }
+
     pub fn from_game_state(game_state: GameState) -> InternalMessage {
         let main_message = MainMessage {
             body: Some(Body::GameState(game_state.to_proto())),
@@ -42,9 +134,55 @@ impl InternalMessage {
         InternalMessage { main_message }
     }
 
-    pub fn from_chat(chat: String) -> InternalMessage {
+    pub fn from_chat(chat: ChatMessage) -> InternalMessage {
+        let main_message = MainMessage {
+            body: Some(Body::ChatMessage(chat.to_proto())),
+        };
+        InternalMessage { main_message }
+    }
+
+    pub fn from_nudge(nudge: Nudge) -> InternalMessage {
+        let main_message = MainMessage {
+            body: Some(Body::Nudge(nudge.to_proto())),
+        };
+        InternalMessage { main_message }
+    }
+
+    pub fn from_ping(ping: Ping) -> InternalMessage {
+        let main_message = MainMessage {
+            body: Some(Body::Ping(ping.to_proto())),
+        };
+        InternalMessage { main_message }
+    }
+
+    pub fn from_hint(hint: Hint) -> InternalMessage {
+        let main_message = MainMessage {
+            body: Some(Body::Hint(hint.to_proto())),
+        };
+        InternalMessage { main_message }
+    }
+
+    pub fn from_possible_moves(possible_moves: PossibleMoves) -> InternalMessage {
+        let main_message = MainMessage {
+            body: Some(Body::PossibleMoves(possible_moves.to_proto())),
+        };
+        InternalMessage { main_message }
+    }
+
+    pub fn from_server_hello() -> InternalMessage {
+        let server_hello = ServerHello {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: CAPABILITIES.iter().map(|c| c.to_string()).collect(),
+        };
+        let main_message = MainMessage {
+            body: Some(Body::ServerHello(server_hello.to_proto())),
+        };
+        InternalMessage { main_message }
+    }
+
+    pub fn from_path_plan(path_plan: PathPlan) -> InternalMessage {
         let main_message = MainMessage {
-            body: Some(Body::Chat(chat)),
+            body: Some(Body::PathPlan(path_plan.to_proto())),
         };
         InternalMessage { main_message }
     }
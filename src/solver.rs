@@ -0,0 +1,190 @@
+// Checks whether a game can still be won, ignoring the timer.
+
+use crate::game::{Game, GameHandle, GameOptions};
+use crate::types::main_message::Body;
+use crate::types::{
+    Ability, GameStatus, Internal, MainMessage, MapPosition, Move, PlaceTile, Player, PlayerName,
+    Presence, SquareType,
+};
+
+use std::collections::{HashSet, VecDeque};
+
+/// Past this many distinct states we give up rather than run forever.
+pub const DEFAULT_MAX_STATES: usize = 100_000;
+
+const SOLVER_PLAYER_NAME: &str = "solver";
+
+// The solver plays as a single player who can do everything.
+const ALL_ABILITIES: [Ability; 7] = [
+    Ability::MoveNorth,
+    Ability::MoveEast,
+    Ability::MoveSouth,
+    Ability::MoveWest,
+    Ability::Teleport,
+    Ability::RevealTiles,
+    Ability::UseEscalator,
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Solvability {
+    Solvable { min_moves: usize },
+    Unsolvable,
+    // We hit the state limit before finding an answer either way.
+    Unknown,
+}
+
+#[derive(Clone, Debug)]
+pub struct SolveReport {
+    pub solvability: Solvability,
+    pub states_explored: usize,
+    // Unused timer squares on the board and in the deck.
+    pub timer_squares: usize,
+}
+
+/// Everything about a game that matters for winning it. The deck order is
+/// fixed, so the placed tiles also tell us what is left to draw.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct SolverState {
+    heisters: Vec<(i32, i32, bool)>,
+    tiles: Vec<(String, i32, i32)>,
+    all_items_taken: bool,
+    timers_used: Vec<(i32, i32)>,
+}
+
+impl SolverState {
+    fn from_game(game: &Game) -> Self {
+        let game_state = &game.game_state;
+        let heisters = game_state
+            .heisters
+            .iter()
+            .map(|h| (h.map_position.x, h.map_position.y, h.has_escaped))
+            .collect();
+        let mut tiles: Vec<(String, i32, i32)> = game_state
+            .tiles
+            .iter()
+            .map(|t| (t.name.clone(), t.position.x, t.position.y))
+            .collect();
+        tiles.sort();
+        let mut timers_used: Vec<(i32, i32)> = game_state
+            .get_absolute_grid()
+            .iter()
+            .filter(|(_, square)| square.square_type == SquareType::TimerFlipUsed)
+            .map(|(pos, _)| (pos.x, pos.y))
+            .collect();
+        timers_used.sort();
+        SolverState {
+            heisters,
+            tiles,
+            all_items_taken: game_state.all_items_taken,
+            timers_used,
+        }
+    }
+}
+
+/// A game with a deck shuffled by the given seed, ready to be solved.
+pub fn new_seeded_game(seed: u64) -> Game {
+    let game_options = GameOptions {
+        seed: Some(seed),
+        ..GameOptions::default()
+    };
+    Game::new(GameHandle(format!("solver-{}", seed)), game_options)
+}
+
+fn count_timer_squares(game: &Game) -> usize {
+    game.game_state
+        .tiles
+        .iter()
+        .chain(game.tile_deck.iter())
+        .flat_map(|t| t.squares.iter())
+        .filter(|s| s.square_type == SquareType::TimerFlip)
+        .count()
+}
+
+/// Every message the solver could send from this position.
+fn next_messages(game: &Game) -> Vec<MainMessage> {
+    let game_state = &game.game_state;
+    let mut bodies = Vec::new();
+    for heister in &game_state.heisters {
+        for position in game_state.get_reachable_positions(
+            heister.heister_color,
+            &ALL_ABILITIES,
+            game.game_options.teleport_only_from_portal,
+        ) {
+            let m = Move {
+                heister_color: heister.heister_color,
+                position,
+            };
+            bodies.push(Body::Move(m.to_proto()));
+        }
+    }
+    let grid = game_state.get_absolute_grid();
+    let tile_entrances: Vec<MapPosition> = game_state
+        .heister_to_tile_entrance_positions(&grid)
+        .values()
+        .cloned()
+        .collect();
+    if !game.tile_deck.is_empty() {
+        for tile_entrance in tile_entrances {
+            let pt = PlaceTile { tile_entrance };
+            bodies.push(Body::PlaceTile(pt.to_proto()));
+        }
+    }
+    bodies
+        .into_iter()
+        .map(|body| MainMessage { body: Some(body) })
+        .collect()
+}
+
+/// Search breadth first from the given game for the fewest moves (including
+/// tile placements) that win it. The players are replaced by a single player
+/// with every ability and the timer is ignored, so this answers whether the
+/// board itself can be beaten.
+pub fn solve(game: &Game, max_states: usize) -> SolveReport {
+    let player_name = PlayerName(SOLVER_PLAYER_NAME.to_string());
+    let mut start = game.clone();
+    start.game_state.players = vec![Player {
+        name: player_name.0.clone(),
+        abilities: ALL_ABILITIES.to_vec(),
+        is_bot: false,
+        presence: Presence::Online,
+    }];
+    // Going straight to Ongoing means the timer never starts, so we can't lose
+    // to the clock while searching.
+    start.game_state.game_status = GameStatus::Ongoing;
+    start.game_state.game_started = 0;
+
+    let timer_squares = count_timer_squares(&start);
+    let report = |solvability, states_explored| SolveReport {
+        solvability,
+        states_explored,
+        timer_squares,
+    };
+
+    let mut seen: HashSet<SolverState> = HashSet::new();
+    seen.insert(SolverState::from_game(&start));
+    let mut queue: VecDeque<(Game, usize)> = VecDeque::new();
+    queue.push_back((start, 0));
+
+    while let Some((current, num_moves)) = queue.pop_front() {
+        for message in next_messages(&current) {
+            let mut next = current.clone();
+            if next.handle_message(message, &player_name).is_invalid() {
+                continue;
+            }
+            if next.game_state.game_status == GameStatus::Victory {
+                let solvability = Solvability::Solvable {
+                    min_moves: num_moves + 1,
+                };
+                return report(solvability, seen.len());
+            }
+            if !seen.insert(SolverState::from_game(&next)) {
+                continue;
+            }
+            if seen.len() >= max_states {
+                return report(Solvability::Unknown, seen.len());
+            }
+            queue.push_back((next, num_moves + 1));
+        }
+    }
+    report(Solvability::Unsolvable, seen.len())
+}
@@ -0,0 +1,203 @@
+// Draws the board as a standalone SVG, for sharing boards without the JS client.
+
+use crate::game_state::GameState;
+use crate::types::{
+    get_heister_letter, get_wall_color, HeisterColor, MapPosition, Square, SquareType, WallType,
+};
+
+const SQUARE_SIZE: i32 = 40;
+const MARGIN: i32 = 10;
+const BACKGROUND: &str = "#ffffff";
+const FLOOR: &str = "#f4f1ea";
+const FILLED: &str = "#555555";
+const GRID_LINE: &str = "#d8d4cc";
+const WALL: &str = "#000000";
+
+/// The same colors as the browser client uses.
+fn heister_color_hex(heister_color: HeisterColor) -> &'static str {
+    match heister_color {
+        HeisterColor::Yellow => "#f0d249",
+        HeisterColor::Purple => "#cb97ef",
+        HeisterColor::Green => "#81ae62",
+        HeisterColor::Orange => "#e78234",
+    }
+}
+
+fn draw_wall(svg: &mut String, wall: WallType, x1: i32, y1: i32, x2: i32, y2: i32) {
+    let (color, width) = match wall {
+        WallType::Clear => return,
+        WallType::Impassable => (WALL, 4),
+        _ => (heister_color_hex(get_wall_color(wall).unwrap()), 6),
+    };
+    svg.push_str(&format!(
+        "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"square\"/>\n",
+        x1, y1, x2, y2, color, width
+    ));
+}
+
+fn draw_square(svg: &mut String, square: &Square, left: i32, top: i32) {
+    let size = SQUARE_SIZE;
+    let mid_x = left + size / 2;
+    let mid_y = top + size / 2;
+    let fill = match square.square_type {
+        SquareType::Filled => FILLED,
+        _ => FLOOR,
+    };
+    svg.push_str(&format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"{}\"/>\n",
+        left, top, size, size, fill, GRID_LINE
+    ));
+
+    let color = square.color().map(heister_color_hex);
+    if square.is_teleport() {
+        svg.push_str(&format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"4\"/>\n",
+            mid_x,
+            mid_y,
+            size / 3,
+            color.unwrap()
+        ));
+    } else if square.is_item() {
+        let r = size / 4;
+        svg.push_str(&format!(
+            "<polygon points=\"{},{} {},{} {},{} {},{}\" fill=\"{}\" stroke=\"{}\"/>\n",
+            mid_x,
+            mid_y - r,
+            mid_x + r,
+            mid_y,
+            mid_x,
+            mid_y + r,
+            mid_x - r,
+            mid_y,
+            color.unwrap(),
+            WALL
+        ));
+    } else if square.is_escape() {
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            left + 4,
+            top + 4,
+            size - 8,
+            size - 8,
+            color.unwrap()
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"10\" text-anchor=\"middle\" dominant-baseline=\"middle\">EXIT</text>\n",
+            mid_x, mid_y
+        ));
+    } else {
+        let (label, label_color) = match square.square_type {
+            SquareType::Escalator => ("Z", WALL),
+            SquareType::TimerFlip => ("T", WALL),
+            SquareType::TimerFlipUsed => ("T", GRID_LINE),
+            _ => ("", WALL),
+        };
+        if !label.is_empty() {
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-size=\"20\" font-family=\"monospace\" text-anchor=\"middle\" dominant-baseline=\"middle\" fill=\"{}\">{}</text>\n",
+                mid_x, mid_y, label_color, label
+            ));
+        }
+    }
+}
+
+fn draw_walls(svg: &mut String, square: &Square, left: i32, top: i32) {
+    let size = SQUARE_SIZE;
+    draw_wall(svg, square.north_wall, left, top, left + size, top);
+    draw_wall(
+        svg,
+        square.east_wall,
+        left + size,
+        top,
+        left + size,
+        top + size,
+    );
+    draw_wall(
+        svg,
+        square.south_wall,
+        left,
+        top + size,
+        left + size,
+        top + size,
+    );
+    draw_wall(svg, square.west_wall, left, top, left, top + size);
+}
+
+/// Draws every revealed tile at its position, with the heisters on top.
+/// Heisters who have escaped are drawn in a row underneath the board.
+pub fn render_svg(game_state: &GameState) -> String {
+    let grid = game_state.get_absolute_grid();
+    let min_x = grid.keys().map(|p| p.x).min().unwrap_or(0);
+    let max_x = grid.keys().map(|p| p.x).max().unwrap_or(0);
+    let min_y = grid.keys().map(|p| p.y).min().unwrap_or(0);
+    let max_y = grid.keys().map(|p| p.y).max().unwrap_or(0);
+    let board_width = (max_x - min_x + 1) * SQUARE_SIZE;
+    let board_height = (max_y - min_y + 1) * SQUARE_SIZE;
+    let width = board_width + MARGIN * 2;
+    let height = board_height + MARGIN * 3 + SQUARE_SIZE;
+    let top_left = |pos: &MapPosition| {
+        (
+            MARGIN + (pos.x - min_x) * SQUARE_SIZE,
+            MARGIN + (pos.y - min_y) * SQUARE_SIZE,
+        )
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    );
+    svg.push_str(&format!(
+        "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+        BACKGROUND
+    ));
+
+    // Sort the squares so the output is the same every time.
+    let mut positions: Vec<&MapPosition> = grid.keys().collect();
+    positions.sort_by_key(|p| (p.y, p.x));
+    for pos in &positions {
+        let (left, top) = top_left(pos);
+        draw_square(&mut svg, &grid[*pos], left, top);
+    }
+    // Walls go on top of all the squares, so neighbors don't cover them.
+    for pos in &positions {
+        let (left, top) = top_left(pos);
+        draw_walls(&mut svg, &grid[*pos], left, top);
+    }
+
+    let mut num_escaped = 0;
+    for heister in &game_state.heisters {
+        let (cx, cy) = match heister.has_escaped {
+            true => {
+                num_escaped += 1;
+                (
+                    MARGIN + (num_escaped - 1) * SQUARE_SIZE + SQUARE_SIZE / 2,
+                    MARGIN * 2 + board_height + SQUARE_SIZE / 2,
+                )
+            }
+            false => {
+                if !grid.contains_key(&heister.map_position) {
+                    continue;
+                }
+                let (left, top) = top_left(&heister.map_position);
+                (left + SQUARE_SIZE / 2, top + SQUARE_SIZE / 2)
+            }
+        };
+        svg.push_str(&format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"2\"/>\n",
+            cx,
+            cy,
+            SQUARE_SIZE * 3 / 8,
+            heister_color_hex(heister.heister_color),
+            WALL
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" font-size=\"16\" font-family=\"monospace\" font-weight=\"bold\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>\n",
+            cx,
+            cy,
+            get_heister_letter(heister.heister_color)
+        ));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
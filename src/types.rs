@@ -1,3 +1,4 @@
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::convert::From;
@@ -14,6 +15,7 @@ pub use proto_types::GameStatus;
 pub use proto_types::HeisterColor;
 pub use proto_types::HeisterSymbol;
 pub use proto_types::PossibleTeleportEntry;
+pub use proto_types::Presence;
 pub use proto_types::SquareType;
 pub use proto_types::WallType;
 
@@ -31,6 +33,21 @@ pub trait Internal {
     fn to_proto(&self) -> Self::P;
 }
 
+/// Like Internal, for messages players send that carry fields they might
+/// leave out or fill with nonsense. These run under the game lock, so a bad
+/// message gets an InvalidRequest back rather than a panic.
+pub trait TryInternal: Sized {
+    type P;
+
+    fn from_proto(proto: Self::P) -> Result<Self>;
+    fn to_proto(&self) -> Self::P;
+}
+
+fn heister_color_from_i32(heister_color: i32) -> Result<HeisterColor> {
+    HeisterColor::from_i32(heister_color)
+        .ok_or_else(|| anyhow!("Unknown heister color {}", heister_color))
+}
+
 pub const TIMER_DURATION_SECS: u64 = 5 * 60;
 
 pub const DOOR_TYPES: [&'static WallType; 4] = [
@@ -73,6 +90,16 @@ pub fn get_wall_color(wall: proto_types::WallType) -> Option<HeisterColor> {
     }
 }
 
+/// The letter used for a heister when drawing the board as text.
+pub fn get_heister_letter(heister_color: HeisterColor) -> char {
+    match heister_color {
+        HeisterColor::Yellow => 'Y',
+        HeisterColor::Purple => 'P',
+        HeisterColor::Green => 'G',
+        HeisterColor::Orange => 'O',
+    }
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct TilePosition {
     x: u32,
@@ -265,6 +292,16 @@ pub enum MoveDirection {
 }
 
 impl MoveDirection {
+    /// The ability a player needs to move heisters in this direction.
+    pub fn ability(&self) -> Ability {
+        match self {
+            MoveDirection::North => Ability::MoveNorth,
+            MoveDirection::East => Ability::MoveEast,
+            MoveDirection::South => Ability::MoveSouth,
+            MoveDirection::West => Ability::MoveWest,
+        }
+    }
+
     pub fn opposite(&self) -> MoveDirection {
         match self {
             MoveDirection::North => MoveDirection::South,
@@ -865,6 +902,8 @@ impl Heister {
 pub struct Player {
     pub name: String,
     pub abilities: Vec<Ability>,
+    pub is_bot: bool,
+    pub presence: Presence,
 }
 
 impl Internal for Player {
@@ -879,6 +918,8 @@ impl Internal for Player {
         Player {
             name: proto.name,
             abilities,
+            is_bot: proto.is_bot,
+            presence: Presence::from_i32(proto.presence).unwrap_or(Presence::Offline),
         }
     }
 
@@ -891,6 +932,8 @@ impl Internal for Player {
         proto_types::Player {
             name: self.name.clone(),
             abilities: proto_abilities,
+            is_bot: self.is_bot,
+            presence: i32::from(self.presence),
         }
     }
 }
@@ -976,6 +1019,504 @@ impl Internal for PlaceTile {
     }
 }
 
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct KickPlayer {
+    pub name: PlayerName,
+}
+
+impl Internal for KickPlayer {
+    type P = proto_types::KickPlayer;
+
+    fn from_proto(proto: proto_types::KickPlayer) -> Self {
+        KickPlayer {
+            name: PlayerName(proto.name),
+        }
+    }
+
+    fn to_proto(&self) -> proto_types::KickPlayer {
+        proto_types::KickPlayer {
+            name: self.name.0.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TransferHost {
+    pub name: PlayerName,
+}
+
+impl Internal for TransferHost {
+    type P = proto_types::TransferHost;
+
+    fn from_proto(proto: proto_types::TransferHost) -> Self {
+        TransferHost {
+            name: PlayerName(proto.name),
+        }
+    }
+
+    fn to_proto(&self) -> proto_types::TransferHost {
+        proto_types::TransferHost {
+            name: self.name.0.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AddBot {}
+
+impl Internal for AddBot {
+    type P = proto_types::AddBot;
+
+    fn from_proto(_proto: proto_types::AddBot) -> Self {
+        AddBot {}
+    }
+
+    fn to_proto(&self) -> proto_types::AddBot {
+        proto_types::AddBot {}
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RemoveBot {
+    pub name: PlayerName,
+}
+
+impl Internal for RemoveBot {
+    type P = proto_types::RemoveBot;
+
+    fn from_proto(proto: proto_types::RemoveBot) -> Self {
+        RemoveBot {
+            name: PlayerName(proto.name),
+        }
+    }
+
+    fn to_proto(&self) -> proto_types::RemoveBot {
+        proto_types::RemoveBot {
+            name: self.name.0.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LeaveGame {}
+
+impl Internal for LeaveGame {
+    type P = proto_types::LeaveGame;
+
+    fn from_proto(_proto: proto_types::LeaveGame) -> Self {
+        LeaveGame {}
+    }
+
+    fn to_proto(&self) -> proto_types::LeaveGame {
+        proto_types::LeaveGame {}
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RenamePlayer {
+    pub name: PlayerName,
+}
+
+impl Internal for RenamePlayer {
+    type P = proto_types::RenamePlayer;
+
+    fn from_proto(proto: proto_types::RenamePlayer) -> Self {
+        RenamePlayer {
+            name: PlayerName(proto.name),
+        }
+    }
+
+    fn to_proto(&self) -> proto_types::RenamePlayer {
+        proto_types::RenamePlayer {
+            name: self.name.0.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub sender: PlayerName,
+    pub timestamp: u64,
+    pub text: String,
+    pub id: u64,
+}
+
+impl Internal for ChatMessage {
+    type P = proto_types::ChatMessage;
+
+    fn from_proto(proto: proto_types::ChatMessage) -> Self {
+        ChatMessage {
+            sender: PlayerName(proto.sender),
+            timestamp: proto.timestamp,
+            text: proto.text,
+            id: proto.id,
+        }
+    }
+
+    fn to_proto(&self) -> proto_types::ChatMessage {
+        proto_types::ChatMessage {
+            sender: self.sender.0.to_string(),
+            timestamp: self.timestamp,
+            text: self.text.to_string(),
+            id: self.id,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Nudge {
+    pub sender: PlayerName,
+    pub target_player: PlayerName,
+    pub heister_color: Option<HeisterColor>,
+    pub map_position: Option<MapPosition>,
+}
+
+impl TryInternal for Nudge {
+    type P = proto_types::Nudge;
+
+    fn from_proto(proto: proto_types::Nudge) -> Result<Self> {
+        let heister_color = match proto.heister {
+            Some(proto_types::nudge::Heister::HeisterColor(hc)) => {
+                Some(heister_color_from_i32(hc)?)
+            }
+            None => None,
+        };
+        Ok(Nudge {
+            sender: PlayerName(proto.sender),
+            target_player: PlayerName(proto.target_player),
+            heister_color,
+            map_position: proto.map_position.map(MapPosition::from_proto),
+        })
+    }
+
+    fn to_proto(&self) -> proto_types::Nudge {
+        proto_types::Nudge {
+            sender: self.sender.0.to_string(),
+            target_player: self.target_player.0.to_string(),
+            heister: self
+                .heister_color
+                .map(|hc| proto_types::nudge::Heister::HeisterColor(i32::from(hc))),
+            map_position: self.map_position.map(|mp| mp.to_proto()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Ping {
+    pub sender: PlayerName,
+    pub map_position: MapPosition,
+    pub heister_color: Option<HeisterColor>,
+}
+
+impl TryInternal for Ping {
+    type P = proto_types::Ping;
+
+    fn from_proto(proto: proto_types::Ping) -> Result<Self> {
+        let heister_color = match proto.heister {
+            Some(proto_types::ping::Heister::HeisterColor(hc)) => Some(heister_color_from_i32(hc)?),
+            None => None,
+        };
+        let map_position = proto
+            .map_position
+            .ok_or_else(|| anyhow!("A ping needs a position"))?;
+        Ok(Ping {
+            sender: PlayerName(proto.sender),
+            map_position: MapPosition::from_proto(map_position),
+            heister_color,
+        })
+    }
+
+    fn to_proto(&self) -> proto_types::Ping {
+        proto_types::Ping {
+            sender: self.sender.0.to_string(),
+            map_position: Some(self.map_position.to_proto()),
+            heister: self
+                .heister_color
+                .map(|hc| proto_types::ping::Heister::HeisterColor(i32::from(hc))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameStats {
+    pub num_moves: u32,
+    pub num_tiles_placed: u32,
+    pub num_hints: u32,
+}
+
+impl Internal for GameStats {
+    type P = proto_types::GameStats;
+
+    fn from_proto(proto: proto_types::GameStats) -> Self {
+        GameStats {
+            num_moves: proto.num_moves,
+            num_tiles_placed: proto.num_tiles_placed,
+            num_hints: proto.num_hints,
+        }
+    }
+
+    fn to_proto(&self) -> proto_types::GameStats {
+        proto_types::GameStats {
+            num_moves: self.num_moves,
+            num_tiles_placed: self.num_tiles_placed,
+            num_hints: self.num_hints,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RequestHint {}
+
+impl Internal for RequestHint {
+    type P = proto_types::RequestHint;
+
+    fn from_proto(_proto: proto_types::RequestHint) -> Self {
+        RequestHint {}
+    }
+
+    fn to_proto(&self) -> proto_types::RequestHint {
+        proto_types::RequestHint {}
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Command {
+    pub text: String,
+}
+
+impl Internal for Command {
+    type P = proto_types::Command;
+
+    fn from_proto(proto: proto_types::Command) -> Self {
+        Command { text: proto.text }
+    }
+
+    fn to_proto(&self) -> proto_types::Command {
+        proto_types::Command {
+            text: self.text.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Hello {
+    pub protocol_version: u32,
+    pub client_name: String,
+}
+
+impl Internal for Hello {
+    type P = proto_types::Hello;
+
+    fn from_proto(proto: proto_types::Hello) -> Self {
+        Hello {
+            protocol_version: proto.protocol_version,
+            client_name: proto.client_name,
+        }
+    }
+
+    fn to_proto(&self) -> proto_types::Hello {
+        proto_types::Hello {
+            protocol_version: self.protocol_version,
+            client_name: self.client_name.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ServerHello {
+    pub protocol_version: u32,
+    pub capabilities: Vec<String>,
+}
+
+impl Internal for ServerHello {
+    type P = proto_types::ServerHello;
+
+    fn from_proto(proto: proto_types::ServerHello) -> Self {
+        ServerHello {
+            protocol_version: proto.protocol_version,
+            capabilities: proto.capabilities,
+        }
+    }
+
+    fn to_proto(&self) -> proto_types::ServerHello {
+        proto_types::ServerHello {
+            protocol_version: self.protocol_version,
+            capabilities: self.capabilities.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hint {
+    pub ability: Ability,
+    pub text: String,
+    pub heister_color: Option<HeisterColor>,
+    pub map_position: MapPosition,
+}
+
+impl Internal for Hint {
+    type P = proto_types::Hint;
+
+    fn from_proto(proto: proto_types::Hint) -> Self {
+        let heister_color = match proto.heister {
+            Some(proto_types::hint::Heister::HeisterColor(hc)) => {
+                Some(HeisterColor::from_i32(hc).unwrap())
+            }
+            None => None,
+        };
+        Hint {
+            ability: Ability::from_i32(proto.ability).unwrap(),
+            text: proto.text,
+            heister_color,
+            map_position: MapPosition::from_proto(proto.map_position.unwrap()),
+        }
+    }
+
+    fn to_proto(&self) -> proto_types::Hint {
+        proto_types::Hint {
+            ability: i32::from(self.ability),
+            text: self.text.clone(),
+            heister: self
+                .heister_color
+                .map(|hc| proto_types::hint::Heister::HeisterColor(i32::from(hc))),
+            map_position: Some(self.map_position.to_proto()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct QueryMoves {
+    pub heister_color: HeisterColor,
+}
+
+impl TryInternal for QueryMoves {
+    type P = proto_types::QueryMoves;
+
+    fn from_proto(proto: proto_types::QueryMoves) -> Result<Self> {
+        Ok(QueryMoves {
+            heister_color: heister_color_from_i32(proto.heister_color)?,
+        })
+    }
+
+    fn to_proto(&self) -> proto_types::QueryMoves {
+        proto_types::QueryMoves {
+            heister_color: i32::from(self.heister_color),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PossibleMoves {
+    pub heister_color: HeisterColor,
+    pub positions: Vec<MapPosition>,
+}
+
+impl Internal for PossibleMoves {
+    type P = proto_types::PossibleMoves;
+
+    fn from_proto(proto: proto_types::PossibleMoves) -> Self {
+        PossibleMoves {
+            heister_color: HeisterColor::from_i32(proto.heister_color).unwrap(),
+            positions: proto
+                .positions
+                .into_iter()
+                .map(MapPosition::from_proto)
+                .collect(),
+        }
+    }
+
+    fn to_proto(&self) -> proto_types::PossibleMoves {
+        proto_types::PossibleMoves {
+            heister_color: i32::from(self.heister_color),
+            positions: self.positions.iter().map(|p| p.to_proto()).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PlanPath {
+    pub heister_color: HeisterColor,
+    pub target: MapPosition,
+}
+
+impl TryInternal for PlanPath {
+    type P = proto_types::PlanPath;
+
+    fn from_proto(proto: proto_types::PlanPath) -> Result<Self> {
+        let target = proto
+            .target
+            .ok_or_else(|| anyhow!("A path needs a target"))?;
+        Ok(PlanPath {
+            heister_color: heister_color_from_i32(proto.heister_color)?,
+            target: MapPosition::from_proto(target),
+        })
+    }
+
+    fn to_proto(&self) -> proto_types::PlanPath {
+        proto_types::PlanPath {
+            heister_color: i32::from(self.heister_color),
+            target: Some(self.target.to_proto()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub position: MapPosition,
+    pub ability: Ability,
+    pub players: Vec<PlayerName>,
+}
+
+impl Internal for PlanStep {
+    type P = proto_types::PlanStep;
+
+    fn from_proto(proto: proto_types::PlanStep) -> Self {
+        PlanStep {
+            position: MapPosition::from_proto(proto.position.unwrap()),
+            ability: Ability::from_i32(proto.ability).unwrap(),
+            players: proto.players.into_iter().map(PlayerName).collect(),
+        }
+    }
+
+    fn to_proto(&self) -> proto_types::PlanStep {
+        proto_types::PlanStep {
+            position: Some(self.position.to_proto()),
+            ability: i32::from(self.ability),
+            players: self.players.iter().map(|p| p.0.clone()).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PathPlan {
+    pub heister_color: HeisterColor,
+    pub target: MapPosition,
+    pub reachable: bool,
+    pub steps: Vec<PlanStep>,
+}
+
+impl Internal for PathPlan {
+    type P = proto_types::PathPlan;
+
+    fn from_proto(proto: proto_types::PathPlan) -> Self {
+        PathPlan {
+            heister_color: HeisterColor::from_i32(proto.heister_color).unwrap(),
+            target: MapPosition::from_proto(proto.target.unwrap()),
+            reachable: proto.reachable,
+            steps: proto.steps.into_iter().map(PlanStep::from_proto).collect(),
+        }
+    }
+
+    fn to_proto(&self) -> proto_types::PathPlan {
+        proto_types::PathPlan {
+            heister_color: i32::from(self.heister_color),
+            target: Some(self.target.to_proto()),
+            reachable: self.reachable,
+            steps: self.steps.iter().map(|s| s.to_proto()).collect(),
+        }
+    }
+}
+
 // JSON Serialization for Tiles
 // Since we can't directly add these derives on the proto_types
 #[derive(Serialize, Deserialize)]
@@ -0,0 +1,95 @@
+use std::collections::{HashMap, HashSet};
+
+use team_heist_tactics::game::{GameHandle, GameOptions};
+use team_heist_tactics::manager::{CreateOptions, GameManager, JoinOptions};
+use team_heist_tactics::types::main_message::Body;
+use team_heist_tactics::types::{GameStatus, Internal, MainMessage, PlayerName, StartGame};
+
+fn create_game(
+    game_manager: &mut GameManager,
+    handle: &str,
+    host: &str,
+    public: bool,
+) -> GameHandle {
+    game_manager
+        .new_game(
+            GameOptions::default(),
+            CreateOptions {
+                handle: Some(handle.to_string()),
+                host: Some(PlayerName(host.to_string())),
+                public,
+                password_hash: None,
+            },
+        )
+        .unwrap()
+}
+
+#[test]
+fn game_summaries_are_sorted_by_handle() {
+    let mut game_manager = GameManager::new(HashMap::new(), HashSet::new());
+    create_game(&mut game_manager, "zebra", "alice", false);
+    create_game(&mut game_manager, "apple", "bob", false);
+
+    let summaries = game_manager.get_game_summaries();
+    let handles: Vec<&str> = summaries.iter().map(|s| s.handle.as_str()).collect();
+    assert_eq!(handles, vec!["apple", "zebra"]);
+    assert_eq!(summaries[0].host, "bob");
+    assert_eq!(summaries[0].game_status, GameStatus::Staging);
+    assert_eq!(summaries[0].num_players, 0);
+
+    let json = serde_json::to_value(&summaries).unwrap();
+    assert_eq!(json[1]["handle"], "zebra");
+    assert_eq!(json[1]["game_status"], "Staging");
+}
+
+#[test]
+fn game_state_serializes_to_json() {
+    let mut game_manager = GameManager::new(HashMap::new(), HashSet::new());
+    let handle = create_game(&mut game_manager, "apple", "bob", false);
+    assert!(game_manager
+        .get_game(&GameHandle("pear".to_string()))
+        .is_err());
+
+    let game_wrapper = game_manager.get_game(&handle).unwrap();
+    let game_state = game_wrapper.read().unwrap().get_game_state().to_proto();
+    let json = serde_json::to_value(&game_state).unwrap();
+    assert_eq!(json["game_name"], "apple");
+    assert_eq!(json["heisters"].as_array().unwrap().len(), 4);
+    assert!(json["players"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn lobby_lists_public_games_in_staging() {
+    let mut game_manager = GameManager::new(HashMap::new(), HashSet::new());
+    create_game(&mut game_manager, "private", "alice", false);
+    create_game(&mut game_manager, "open", "bob", true);
+    let started = create_game(&mut game_manager, "started", "carol", true);
+    let join_options = JoinOptions {
+        name: "carol".to_string(),
+        handle: started.clone(),
+        spectate: false,
+    };
+    game_manager.join_game(join_options).unwrap();
+    let start_game = MainMessage {
+        body: Some(Body::StartGame(StartGame {}.to_proto())),
+    };
+    let game_wrapper = game_manager.get_game(&started).unwrap();
+    let validity = game_wrapper
+        .write()
+        .unwrap()
+        .handle_message(start_game, &PlayerName("carol".to_string()));
+    assert!(validity.is_valid());
+
+    let public_handles: Vec<String> = game_manager
+        .get_public_games()
+        .into_iter()
+        .map(|s| s.handle)
+        .collect();
+    assert_eq!(public_handles, vec!["open", "started"]);
+
+    let lobby = game_manager.get_lobby_games();
+    assert_eq!(lobby.len(), 1);
+    assert_eq!(lobby[0].handle, "open");
+    assert_eq!(lobby[0].max_players, 8);
+    assert!(!lobby[0].enforce_silence);
+}
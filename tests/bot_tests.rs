@@ -0,0 +1,89 @@
+use team_heist_tactics::bot::{PlayerAgent, SimpleBot};
+use team_heist_tactics::game::{Game, GameHandle, GameOptions, MoveValidity};
+use team_heist_tactics::types::{
+    main_message::Body, AddBot, GameStatus, Internal, MainMessage, PlayerName, RemoveBot, StartGame,
+};
+
+const HOST_NAME: &str = "host";
+
+fn setup_staging_game(handle: &str) -> Game {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let game_options = GameOptions {
+        shuffle_tiles: false,
+        ..GameOptions::default()
+    };
+    let mut game = Game::new(GameHandle(handle.to_string()), game_options);
+    game.add_player(HOST_NAME.to_string()).unwrap();
+    game
+}
+
+fn add_bot() -> MainMessage {
+    MainMessage {
+        body: Some(Body::AddBot(AddBot {}.to_proto())),
+    }
+}
+
+#[test]
+fn host_can_add_and_remove_bots() {
+    let mut game = setup_staging_game("host can add and remove bots");
+    let host = PlayerName(HOST_NAME.to_string());
+
+    assert_eq!(game.handle_message(add_bot(), &host), MoveValidity::Valid);
+    assert_eq!(game.handle_message(add_bot(), &host), MoveValidity::Valid);
+    let bot_name = PlayerName("Bot 1".to_string());
+    assert!(game.game_state.is_bot(&bot_name));
+    assert!(game.game_state.is_bot(&PlayerName("Bot 2".to_string())));
+
+    // Bots can't manage the game.
+    assert!(game.handle_message(add_bot(), &bot_name).is_invalid());
+
+    // Only bots can be removed this way.
+    let remove = |name: &str| MainMessage {
+        body: Some(Body::RemoveBot(
+            RemoveBot {
+                name: PlayerName(name.to_string()),
+            }
+            .to_proto(),
+        )),
+    };
+    assert!(game.handle_message(remove(HOST_NAME), &host).is_invalid());
+    assert_eq!(
+        game.handle_message(remove("Bot 1"), &host),
+        MoveValidity::Valid
+    );
+    assert!(!game.has_player("Bot 1"));
+
+    // Not once the game has started though.
+    let start = MainMessage {
+        body: Some(Body::StartGame(StartGame {}.to_proto())),
+    };
+    assert_eq!(game.handle_message(start, &host), MoveValidity::Valid);
+    assert!(game.handle_message(add_bot(), &host).is_invalid());
+    assert!(game.handle_message(remove("Bot 2"), &host).is_invalid());
+}
+
+#[test]
+fn bot_only_makes_valid_moves() {
+    let mut game = setup_staging_game("bot only makes valid moves");
+    let host = PlayerName(HOST_NAME.to_string());
+    game.handle_message(add_bot(), &host);
+    let start = MainMessage {
+        body: Some(Body::StartGame(StartGame {}.to_proto())),
+    };
+    game.handle_message(start, &host);
+
+    let bot_name = PlayerName("Bot 1".to_string());
+    let mut bot = SimpleBot::new(bot_name.clone(), false);
+    let mut num_moves = 0;
+    for _ in 0..10 {
+        bot.observe(&game.game_state);
+        let message = match bot.next_message() {
+            Some(message) => message,
+            None => break,
+        };
+        assert_eq!(game.handle_message(message, &bot_name), MoveValidity::Valid);
+        num_moves += 1;
+    }
+    assert!(num_moves > 0);
+    assert_eq!(game.game_state.game_status, GameStatus::Ongoing);
+}
@@ -0,0 +1,64 @@
+use team_heist_tactics::chat::{
+    ChatLog, PlayerRateLimiter, CHAT_HISTORY_LENGTH, CHAT_RATE_LIMIT_MESSAGES, MAX_CHAT_LENGTH,
+};
+use team_heist_tactics::types::PlayerName;
+
+#[test]
+fn chat_messages_are_numbered_and_stored() {
+    let mut chat_log = ChatLog::new();
+    let sender = PlayerName("sender".to_string());
+    let first = chat_log.add_message(&sender, "hello".to_string()).unwrap();
+    let second = chat_log
+        .add_message(&sender, " there ".to_string())
+        .unwrap();
+    assert_eq!(first.sender, sender);
+    assert_eq!(first.id + 1, second.id);
+    assert_eq!(second.text, "there");
+
+    let history = chat_log.get_history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].text, "hello");
+}
+
+#[test]
+fn chat_messages_are_limited() {
+    let mut chat_log = ChatLog::new();
+    let sender = PlayerName("sender".to_string());
+    assert!(chat_log.add_message(&sender, "".to_string()).is_err());
+    assert!(chat_log
+        .add_message(&sender, "x".repeat(MAX_CHAT_LENGTH + 1))
+        .is_err());
+
+    for _ in 0..CHAT_RATE_LIMIT_MESSAGES {
+        chat_log.add_message(&sender, "spam".to_string()).unwrap();
+    }
+    assert!(chat_log.add_message(&sender, "spam".to_string()).is_err());
+
+    // Other players are unaffected.
+    let other = PlayerName("other".to_string());
+    assert!(chat_log.add_message(&other, "hi".to_string()).is_ok());
+}
+
+#[test]
+fn chat_history_is_bounded() {
+    let mut chat_log = ChatLog::new();
+    for i in 0..(CHAT_HISTORY_LENGTH + 10) {
+        let sender = PlayerName(format!("player {}", i));
+        chat_log.add_message(&sender, "hi".to_string()).unwrap();
+    }
+    let history = chat_log.get_history();
+    assert_eq!(history.len(), CHAT_HISTORY_LENGTH);
+    assert_eq!(history[0].id, 10);
+}
+
+#[test]
+fn rate_limiter_window_slides() {
+    let mut rate_limiter = PlayerRateLimiter::new(2, 10);
+    let player = PlayerName("player".to_string());
+    assert!(rate_limiter.try_send(&player, 100));
+    assert!(rate_limiter.try_send(&player, 105));
+    assert!(!rate_limiter.try_send(&player, 109));
+    // The first send has now left the window.
+    assert!(rate_limiter.try_send(&player, 110));
+    assert!(!rate_limiter.try_send(&player, 114));
+}
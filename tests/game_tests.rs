@@ -5,15 +5,20 @@ extern crate lazy_static;
 
 use log::info;
 use std::collections::HashMap;
+use team_heist_tactics::command::parse_command;
 use team_heist_tactics::game::{Game, GameHandle, GameOptions, MoveValidity};
+use team_heist_tactics::game_state::GameState;
 use team_heist_tactics::load_map::{tile_1a, tile_2, tile_5, tile_8};
 use team_heist_tactics::types::{
-    main_message::Body, Heister, HeisterColor, Internal, MainMessage, MapPosition, Move,
-    MoveDirection, PlaceTile, PlayerName, Square, Tile, HEISTER_COLORS,
+    main_message::Body, Command, GameStatus, Heister, HeisterColor, Internal, KickPlayer,
+    LeaveGame, MainMessage, MapPosition, Move, MoveDirection, Nudge, Ping, PlaceTile, PlanPath,
+    PlayerName, Presence, QueryMoves, RenamePlayer, Square, StartGame, Tile, TransferHost,
+    TryInternal, HEISTER_COLORS,
 };
 
 lazy_static! {
     static ref FAKE_PLAYER_NAME: PlayerName = PlayerName("fake name".to_string());
+    static ref OTHER_PLAYER_NAME: PlayerName = PlayerName("other name".to_string());
 }
 
 fn setup_game(handle: String) -> Game {
@@ -26,6 +31,18 @@ fn setup_game(handle: String) -> Game {
     game
 }
 
+/// Sets up a game that hasn't started yet, with FAKE_PLAYER_NAME as host
+/// and OTHER_PLAYER_NAME as a second player.
+fn setup_staging_game(handle: String) -> Game {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let game_handle = GameHandle(handle);
+    let game_options = GameOptions::default();
+    let mut game = Game::new(game_handle, game_options);
+    game.add_player(FAKE_PLAYER_NAME.0.clone()).unwrap();
+    game.add_player(OTHER_PLAYER_NAME.0.clone()).unwrap();
+    game
+}
+
 /// Adjacent square movement for heisters, to make testing easier
 /// Asserts that move was valid & that position is correct for valid move
 fn move_heister_in_dir(
@@ -65,7 +82,12 @@ fn move_heister_in_dir(
         body: Some(Body::Move(test_move.to_proto())),
     };
     let validity = game.handle_message(message, &FAKE_PLAYER_NAME);
-    assert_eq!(validity, expected_validity);
+    assert_eq!(
+        validity,
+        expected_validity,
+        "\n{}",
+        game.game_state.render_ascii()
+    );
     match validity.clone() {
         MoveValidity::Valid => {
             let curr_heister_pos = &game
@@ -73,7 +95,12 @@ fn move_heister_in_dir(
                 .get_heister_from_vec(heister_color)
                 .unwrap()
                 .map_position;
-            assert_eq!(curr_heister_pos, &position);
+            assert_eq!(
+                curr_heister_pos,
+                &position,
+                "\n{}",
+                game.game_state.render_ascii()
+            );
         }
         _invalid => {}
     }
@@ -94,7 +121,12 @@ fn place_first_tile_for_color(
         body: Some(Body::PlaceTile(tile_placement.to_proto())),
     };
     let validity = game.handle_message(message, &FAKE_PLAYER_NAME);
-    assert_eq!(validity, expected_validity);
+    assert_eq!(
+        validity,
+        expected_validity,
+        "\n{}",
+        game.game_state.render_ascii()
+    );
 
     for tile in &game.game_state.tiles {
         if tile.name == "1a".to_string() {
@@ -487,3 +519,509 @@ pub fn test_ability_check() -> () {
         MoveValidity::Invalid("You cannot move heisters South".to_string()),
     );
 }
+
+#[test]
+pub fn test_only_host_can_start_game() -> () {
+    let handle = "only host can start game".to_string();
+    let mut game = setup_staging_game(handle);
+    assert_eq!(game.game_state.host, *FAKE_PLAYER_NAME);
+
+    let message = MainMessage {
+        body: Some(Body::StartGame(StartGame {}.to_proto())),
+    };
+    let validity = game.handle_message(message.clone(), &OTHER_PLAYER_NAME);
+    assert!(validity.is_invalid());
+    assert_eq!(game.game_state.game_status, GameStatus::Staging);
+
+    let validity = game.handle_message(message, &FAKE_PLAYER_NAME);
+    assert_eq!(validity, MoveValidity::Valid);
+    assert_eq!(game.game_state.game_status, GameStatus::PreFirstMove);
+}
+
+#[test]
+pub fn test_first_player_in_is_host_if_host_never_joins() -> () {
+    let game_handle = GameHandle("host never joins".to_string());
+    let mut game = Game::new(game_handle, GameOptions::default());
+    game.game_state.host = PlayerName("creator".to_string());
+    game.add_player(OTHER_PLAYER_NAME.0.clone()).unwrap();
+    assert_eq!(game.game_state.host, *OTHER_PLAYER_NAME);
+    game.add_player(FAKE_PLAYER_NAME.0.clone()).unwrap();
+    assert_eq!(game.game_state.host, *OTHER_PLAYER_NAME);
+}
+
+#[test]
+pub fn test_host_can_kick_player() -> () {
+    let handle = "host can kick player".to_string();
+    let mut game = setup_staging_game(handle);
+
+    // Only the host may kick, and not themselves.
+    let kick = |name: &PlayerName| MainMessage {
+        body: Some(Body::KickPlayer(
+            KickPlayer { name: name.clone() }.to_proto(),
+        )),
+    };
+    let validity = game.handle_message(kick(&FAKE_PLAYER_NAME), &OTHER_PLAYER_NAME);
+    assert!(validity.is_invalid());
+    let validity = game.handle_message(kick(&FAKE_PLAYER_NAME), &FAKE_PLAYER_NAME);
+    assert!(validity.is_invalid());
+
+    let validity = game.handle_message(kick(&OTHER_PLAYER_NAME), &FAKE_PLAYER_NAME);
+    assert_eq!(validity, MoveValidity::Valid);
+    assert!(!game.has_player(&OTHER_PLAYER_NAME.0));
+
+    // The kicked player can no longer do anything.
+    let validity = game.handle_message(kick(&FAKE_PLAYER_NAME), &OTHER_PLAYER_NAME);
+    assert_eq!(
+        validity,
+        MoveValidity::Invalid("You are not a player in this game".to_string())
+    );
+}
+
+#[test]
+pub fn test_host_transfer_and_migration() -> () {
+    let handle = "host transfer and migration".to_string();
+    let mut game = setup_staging_game(handle);
+
+    let message = MainMessage {
+        body: Some(Body::TransferHost(
+            TransferHost {
+                name: OTHER_PLAYER_NAME.clone(),
+            }
+            .to_proto(),
+        )),
+    };
+    let validity = game.handle_message(message, &FAKE_PLAYER_NAME);
+    assert_eq!(validity, MoveValidity::Valid);
+    assert_eq!(game.game_state.host, *OTHER_PLAYER_NAME);
+
+    // The new host disconnects, the only other connected player takes over.
+    game.migrate_host(&[FAKE_PLAYER_NAME.clone()]);
+    assert_eq!(game.game_state.host, *FAKE_PLAYER_NAME);
+
+    // Nobody else is connected, so the host stays the same.
+    game.migrate_host(&[]);
+    assert_eq!(game.game_state.host, *FAKE_PLAYER_NAME);
+}
+
+#[test]
+pub fn test_rename_and_leave_during_staging() -> () {
+    let handle = "rename and leave during staging".to_string();
+    let mut game = setup_staging_game(handle);
+
+    let rename = |name: &str| MainMessage {
+        body: Some(Body::RenamePlayer(
+            RenamePlayer {
+                name: PlayerName(name.to_string()),
+            }
+            .to_proto(),
+        )),
+    };
+    // Names must be unique, non-empty and not too long.
+    assert!(game
+        .handle_message(rename(&OTHER_PLAYER_NAME.0), &FAKE_PLAYER_NAME)
+        .is_invalid());
+    assert!(game
+        .handle_message(rename(""), &FAKE_PLAYER_NAME)
+        .is_invalid());
+    assert!(game
+        .handle_message(rename(&"x".repeat(100)), &FAKE_PLAYER_NAME)
+        .is_invalid());
+
+    // Renaming the host keeps them the host.
+    let validity = game.handle_message(rename("new name"), &FAKE_PLAYER_NAME);
+    assert_eq!(validity, MoveValidity::Valid);
+    let new_name = PlayerName("new name".to_string());
+    assert!(game.has_player(&new_name.0));
+    assert!(!game.has_player(&FAKE_PLAYER_NAME.0));
+    assert_eq!(game.game_state.host, new_name);
+
+    // When the host leaves, the next player becomes host.
+    let leave = MainMessage {
+        body: Some(Body::LeaveGame(LeaveGame {}.to_proto())),
+    };
+    let validity = game.handle_message(leave.clone(), &new_name);
+    assert_eq!(validity, MoveValidity::Valid);
+    assert_eq!(game.game_state.players.len(), 1);
+    assert_eq!(game.game_state.host, *OTHER_PLAYER_NAME);
+
+    // Once the game has started, nobody can leave or rename.
+    game.start_game();
+    assert!(game.handle_message(leave, &OTHER_PLAYER_NAME).is_invalid());
+    assert!(game
+        .handle_message(rename("another name"), &OTHER_PLAYER_NAME)
+        .is_invalid());
+}
+
+#[test]
+pub fn test_silent_play_blocks_chat() -> () {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let game_handle = GameHandle("silent play blocks chat".to_string());
+    let game_options = GameOptions {
+        enforce_silence: true,
+        ..GameOptions::default()
+    };
+    let mut game = Game::new(game_handle, game_options);
+    game.add_player(FAKE_PLAYER_NAME.0.clone()).unwrap();
+    game.start_game();
+
+    // Before the first move, anyone can talk.
+    assert_eq!(game.validate_chat("hello"), MoveValidity::Valid);
+
+    // After a regular move, only non-verbal signals are allowed.
+    move_heister_in_dir(
+        &mut game,
+        HeisterColor::Purple,
+        MoveDirection::North,
+        MoveValidity::Valid,
+    );
+    assert!(!game.game_state.players_may_speak);
+    assert!(game.validate_chat("go north!").is_invalid());
+    assert_eq!(game.validate_chat("!"), MoveValidity::Valid);
+
+    // Flipping the timer lets everyone talk again. Purple is now next to it.
+    move_heister_in_dir(
+        &mut game,
+        HeisterColor::Purple,
+        MoveDirection::West,
+        MoveValidity::Valid,
+    );
+    assert!(game.game_state.players_may_speak);
+    assert_eq!(game.validate_chat("go north!"), MoveValidity::Valid);
+}
+
+#[test]
+pub fn test_nudge_validation() -> () {
+    let handle = "nudge validation".to_string();
+    let game = setup_staging_game(handle);
+    let nudge = Nudge {
+        sender: PlayerName::default(),
+        target_player: OTHER_PLAYER_NAME.clone(),
+        heister_color: Some(HeisterColor::Green),
+        map_position: Some(MapPosition { x: 3, y: 3 }),
+    };
+    assert_eq!(game.validate_nudge(&nudge), MoveValidity::Valid);
+
+    let off_map = Nudge {
+        map_position: Some(MapPosition { x: 10, y: 10 }),
+        ..nudge.clone()
+    };
+    assert!(game.validate_nudge(&off_map).is_invalid());
+
+    let nobody = Nudge {
+        target_player: PlayerName("nobody".to_string()),
+        ..nudge
+    };
+    assert!(game.validate_nudge(&nobody).is_invalid());
+}
+
+#[test]
+pub fn test_ping_must_be_on_map() -> () {
+    let handle = "ping must be on map".to_string();
+    let game = setup_staging_game(handle);
+    let ping = Ping {
+        sender: PlayerName::default(),
+        map_position: MapPosition { x: 0, y: 0 },
+        heister_color: None,
+    };
+    assert_eq!(game.validate_ping(&ping), MoveValidity::Valid);
+
+    let off_map = Ping {
+        map_position: MapPosition { x: -1, y: 0 },
+        heister_color: Some(HeisterColor::Yellow),
+        ..ping
+    };
+    assert!(game.validate_ping(&off_map).is_invalid());
+}
+
+#[test]
+pub fn test_ping_without_position_is_invalid() -> () {
+    let handle = "ping without position".to_string();
+    let mut game = setup_game(handle);
+    let ping = Ping {
+        sender: FAKE_PLAYER_NAME.clone(),
+        ..Ping::default()
+    };
+    let mut proto = ping.to_proto();
+    proto.map_position = None;
+    let message = MainMessage {
+        body: Some(Body::Ping(proto)),
+    };
+    assert!(game.handle_message(message, &FAKE_PLAYER_NAME).is_invalid());
+}
+
+#[test]
+pub fn query_moves_matches_valid_moves() -> () {
+    let handle = "reachable positions are all valid moves".to_string();
+    let game = setup_game(handle);
+
+    for heister_color in HEISTER_COLORS.iter() {
+        let possible_moves = game
+            .query_moves(
+                QueryMoves {
+                    heister_color: **heister_color,
+                },
+                &FAKE_PLAYER_NAME,
+            )
+            .unwrap();
+        assert!(!possible_moves.positions.is_empty());
+        for position in possible_moves.positions {
+            // Replay the move on a fresh game so each one starts from the same state.
+            let mut fresh_game = setup_game("reachable positions fresh".to_string());
+            let message = MainMessage {
+                body: Some(Body::Move(
+                    Move {
+                        heister_color: **heister_color,
+                        position,
+                    }
+                    .to_proto(),
+                )),
+            };
+            let validity = fresh_game.handle_message(message, &FAKE_PLAYER_NAME);
+            assert_eq!(
+                validity,
+                MoveValidity::Valid,
+                "{:?} to {:?}",
+                heister_color,
+                position
+            );
+        }
+    }
+}
+
+#[test]
+pub fn query_moves_includes_escalator() -> () {
+    let handle = "reachable positions include escalators".to_string();
+    let mut game = setup_game(handle);
+    move_heister_in_dir(
+        &mut game,
+        HeisterColor::Green,
+        MoveDirection::South,
+        MoveValidity::Valid,
+    );
+    let possible_moves = game
+        .query_moves(
+            QueryMoves {
+                heister_color: HeisterColor::Green,
+            },
+            &FAKE_PLAYER_NAME,
+        )
+        .unwrap();
+    assert!(possible_moves
+        .positions
+        .contains(&MapPosition { x: 3, y: 2 }));
+
+    // Heisters can't be moved onto each other.
+    for heister in &game.game_state.heisters {
+        assert!(!possible_moves.positions.contains(&heister.map_position));
+    }
+
+    // Only players in the game can ask.
+    let query = QueryMoves {
+        heister_color: HeisterColor::Green,
+    };
+    assert!(game.query_moves(query, &OTHER_PLAYER_NAME).is_err());
+}
+
+#[test]
+pub fn plan_path_steps_are_valid_moves() -> () {
+    let handle = "planned steps are valid moves".to_string();
+    let mut game = setup_game(handle);
+    let target = MapPosition { x: 3, y: 2 };
+    let path_plan = game
+        .plan_path(
+            PlanPath {
+                heister_color: HeisterColor::Green,
+                target,
+            },
+            &FAKE_PLAYER_NAME,
+        )
+        .unwrap();
+    assert!(path_plan.reachable);
+    assert!(!path_plan.steps.is_empty());
+
+    for step in path_plan.steps {
+        assert!(step.players.contains(&FAKE_PLAYER_NAME));
+        let message = MainMessage {
+            body: Some(Body::Move(
+                Move {
+                    heister_color: HeisterColor::Green,
+                    position: step.position,
+                }
+                .to_proto(),
+            )),
+        };
+        let validity = game.handle_message(message, &FAKE_PLAYER_NAME);
+        assert_eq!(validity, MoveValidity::Valid, "{:?}", step);
+    }
+    let green_pos = game
+        .game_state
+        .get_heister_from_vec(HeisterColor::Green)
+        .unwrap()
+        .map_position;
+    assert_eq!(green_pos, target);
+
+    // Somewhere off the map can't be reached.
+    let path_plan = game
+        .plan_path(
+            PlanPath {
+                heister_color: HeisterColor::Green,
+                target: MapPosition { x: 50, y: 50 },
+            },
+            &FAKE_PLAYER_NAME,
+        )
+        .unwrap();
+    assert!(!path_plan.reachable);
+    assert!(path_plan.steps.is_empty());
+}
+
+#[test]
+pub fn hints_have_cooldown_and_are_counted() -> () {
+    let handle = "hints have a cooldown and are counted".to_string();
+    let mut game = setup_game(handle);
+
+    let hint = game.request_hint(&FAKE_PLAYER_NAME).unwrap();
+    assert!(hint.text.starts_with("Someone with"));
+    assert_eq!(game.game_state.stats.num_hints, 1);
+
+    // Following the hint is a valid move.
+    let hint_move = Move {
+        heister_color: hint.heister_color.unwrap(),
+        position: hint.map_position,
+    };
+    let message = MainMessage {
+        body: Some(Body::Move(hint_move.to_proto())),
+    };
+    assert_eq!(
+        game.handle_message(message, &FAKE_PLAYER_NAME),
+        MoveValidity::Valid
+    );
+    assert_eq!(game.game_state.stats.num_moves, 1);
+
+    // Asking again straight away is too soon.
+    assert!(game.request_hint(&FAKE_PLAYER_NAME).is_err());
+    assert_eq!(game.game_state.stats.num_hints, 1);
+}
+
+#[test]
+pub fn commands_become_moves() -> () {
+    let handle = "text commands become moves".to_string();
+    let mut game = setup_game(handle);
+
+    let body = parse_command("  Green South ", &game.game_state).unwrap();
+    let expected = Move {
+        heister_color: HeisterColor::Green,
+        position: MapPosition { x: 2, y: 3 },
+    };
+    assert_eq!(body, Body::Move(expected.to_proto()));
+
+    for text in &["green s", "green escalator"] {
+        let message = MainMessage {
+            body: Some(Body::Command(
+                Command {
+                    text: text.to_string(),
+                }
+                .to_proto(),
+            )),
+        };
+        let validity = game.handle_message(message, &FAKE_PLAYER_NAME);
+        assert_eq!(validity, MoveValidity::Valid, "{}", text);
+    }
+    let green_pos = game
+        .game_state
+        .get_heister_from_vec(HeisterColor::Green)
+        .unwrap()
+        .map_position;
+    assert_eq!(green_pos, MapPosition { x: 3, y: 2 });
+
+    for text in &[
+        "",
+        "blue north",
+        "green up",
+        "green north 0",
+        "green north 1000",
+        "green north 18446744073709551615",
+        "purple escalator",
+        "green teleport 9",
+        "reveal yellow",
+    ] {
+        assert!(parse_command(text, &game.game_state).is_err(), "{}", text);
+    }
+}
+
+#[test]
+pub fn render_ascii_shows_whole_board() -> () {
+    let handle = "render whole board".to_string();
+    let mut game = setup_game(handle);
+    move_heister_in_dir(
+        &mut game,
+        HeisterColor::Orange,
+        MoveDirection::North,
+        MoveValidity::Valid,
+    );
+    place_first_tile_for_color(
+        &mut game,
+        HeisterColor::Orange,
+        MapPosition { x: 2, y: -1 },
+        MoveValidity::Valid,
+    );
+
+    // The new tile is at y = -4, so the board is 8 squares tall and 5 wide,
+    // at 3 characters per square.
+    let board = game.game_state.render_ascii();
+    let lines: Vec<&str> = board.lines().collect();
+    assert_eq!(lines.len(), 24, "\n{}", board);
+    assert!(lines.iter().all(|line| line.len() == 15), "\n{}", board);
+    // Orange is now at (2, 0), 4 squares down from the top of the board.
+    assert_eq!(lines[13].chars().nth(7), Some('O'), "\n{}", board);
+    assert!(!board.contains("Escaped"));
+
+    game.game_state
+        .get_mut_heister_from_vec(HeisterColor::Orange)
+        .unwrap()
+        .has_escaped = true;
+    let board = game.game_state.render_ascii();
+    let lines: Vec<&str> = board.lines().collect();
+    assert_ne!(lines[13].chars().nth(7), Some('O'), "\n{}", board);
+    assert_eq!(lines.last(), Some(&"Escaped: O"));
+}
+
+#[test]
+fn presence_is_tracked_per_player() {
+    let mut game = setup_staging_game("test".to_string());
+    let bot_name = game.game_state.add_bot().unwrap();
+    let presence = |game: &Game, name: &PlayerName| {
+        game.game_state
+            .players
+            .iter()
+            .find(|p| p.name == name.0)
+            .unwrap()
+            .presence
+    };
+    // Players are offline until they connect, bots are always there.
+    assert_eq!(presence(&game, &FAKE_PLAYER_NAME), Presence::Offline);
+    assert_eq!(presence(&game, &bot_name), Presence::Online);
+
+    assert!(game
+        .game_state
+        .set_presence(&FAKE_PLAYER_NAME, Presence::Online));
+    assert!(!game
+        .game_state
+        .set_presence(&FAKE_PLAYER_NAME, Presence::Online));
+    assert!(game
+        .game_state
+        .set_presence(&FAKE_PLAYER_NAME, Presence::Away));
+    assert!(!game
+        .game_state
+        .set_presence(&PlayerName("nobody".to_string()), Presence::Online));
+
+    // Presence is sent to clients with the rest of the state.
+    let game_state = GameState::from_proto(game.game_state.to_proto());
+    assert_eq!(
+        game_state
+            .players
+            .iter()
+            .map(|p| p.presence)
+            .collect::<Vec<Presence>>(),
+        vec![Presence::Away, Presence::Offline, Presence::Online]
+    );
+}
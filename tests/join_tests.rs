@@ -0,0 +1,163 @@
+use std::collections::{HashMap, HashSet};
+
+use team_heist_tactics::close_reason::CloseReason;
+use team_heist_tactics::game::{GameHandle, GameOptions};
+use team_heist_tactics::manager::{CreateOptions, GameManager, JoinOptions};
+use team_heist_tactics::password::{check_password, hash_password, verify_password};
+use team_heist_tactics::types::main_message::Body;
+use team_heist_tactics::types::{Internal, MainMessage, PlayerName, StartGame};
+
+const HANDLE: &str = "apple";
+
+fn make_game_manager() -> GameManager {
+    let mut game_manager = GameManager::new(HashMap::new(), HashSet::new());
+    game_manager
+        .new_game(
+            GameOptions::default(),
+            CreateOptions {
+                handle: Some(HANDLE.to_string()),
+                host: None,
+                public: false,
+                password_hash: None,
+            },
+        )
+        .unwrap();
+    game_manager
+}
+
+fn join(
+    game_manager: &mut GameManager,
+    name: &str,
+    handle: &str,
+    spectate: bool,
+) -> Result<(), CloseReason> {
+    let join_options = JoinOptions {
+        name: name.to_string(),
+        handle: GameHandle(handle.to_string()),
+        spectate,
+    };
+    game_manager.join_game(join_options).map(|_| ())
+}
+
+#[test]
+fn join_failures_have_close_reasons() {
+    let mut game_manager = make_game_manager();
+    assert_eq!(
+        join(&mut game_manager, "alice", "pear", false),
+        Err(CloseReason::GameNotFound("pear".to_string()))
+    );
+    assert!(matches!(
+        join(&mut game_manager, "", HANDLE, false),
+        Err(CloseReason::InvalidName(_))
+    ));
+
+    for i in 0..8 {
+        join(&mut game_manager, &format!("player{}", i), HANDLE, false).unwrap();
+    }
+    assert_eq!(
+        join(&mut game_manager, "alice", HANDLE, false),
+        Err(CloseReason::GameFull)
+    );
+    // Players already in the game can always reconnect.
+    join(&mut game_manager, "player0", HANDLE, false).unwrap();
+    // Spectators can watch a full game, but aren't added to it.
+    join(&mut game_manager, "alice", HANDLE, true).unwrap();
+    let game_wrapper = game_manager
+        .get_game(&GameHandle(HANDLE.to_string()))
+        .unwrap();
+    assert_eq!(game_wrapper.read().unwrap().get_summary().num_players, 8);
+}
+
+#[test]
+fn cannot_join_started_game() {
+    let mut game_manager = make_game_manager();
+    join(&mut game_manager, "alice", HANDLE, false).unwrap();
+    let game_wrapper = game_manager
+        .get_game(&GameHandle(HANDLE.to_string()))
+        .unwrap();
+    let start_game = MainMessage {
+        body: Some(Body::StartGame(StartGame {}.to_proto())),
+    };
+    let validity = game_wrapper
+        .write()
+        .unwrap()
+        .handle_message(start_game, &PlayerName("alice".to_string()));
+    assert!(validity.is_valid());
+
+    assert_eq!(
+        join(&mut game_manager, "bob", HANDLE, false),
+        Err(CloseReason::GameAlreadyStarted)
+    );
+    join(&mut game_manager, "bob", HANDLE, true).unwrap();
+    join(&mut game_manager, "alice", HANDLE, false).unwrap();
+}
+
+#[test]
+fn close_reasons_use_application_codes() {
+    let reasons = vec![
+        CloseReason::GameNotFound("pear".to_string()),
+        CloseReason::GameFull,
+        CloseReason::GameAlreadyStarted,
+        CloseReason::BadPassword,
+        CloseReason::InvalidName("Name is too long".to_string()),
+        CloseReason::IncompatibleProtocol("Too old".to_string()),
+        CloseReason::Kicked,
+        CloseReason::LeftGame,
+        CloseReason::GameReaped,
+        CloseReason::RateLimited,
+        CloseReason::TooManyPasswordAttempts,
+        CloseReason::JoinedElsewhere,
+    ];
+    let codes: HashSet<u16> = reasons.iter().map(|r| r.code()).collect();
+    assert_eq!(codes.len(), reasons.len());
+    assert!(codes.iter().all(|c| (4000..5000).contains(c)));
+    assert_eq!(
+        reasons[0].description(),
+        "Game with handle \"pear\" does not exist"
+    );
+}
+
+#[test]
+fn games_keep_only_the_password_hash() {
+    let mut game_manager = GameManager::new(HashMap::new(), HashSet::new());
+    let handle = game_manager
+        .new_game(
+            GameOptions::default(),
+            CreateOptions {
+                handle: Some(HANDLE.to_string()),
+                host: None,
+                public: false,
+                password_hash: Some(hash_password("hunter2")),
+            },
+        )
+        .unwrap();
+    let password_hash = game_manager.get_password_hash(&handle);
+    assert!(!check_password(password_hash.as_deref(), None));
+    assert!(!check_password(password_hash.as_deref(), Some("hunter3")));
+    assert!(check_password(password_hash.as_deref(), Some("hunter2")));
+
+    let summary = game_manager
+        .get_game(&handle)
+        .unwrap()
+        .read()
+        .unwrap()
+        .get_summary();
+    assert!(summary.has_password);
+
+    // Games without a password let anyone in.
+    let other_game_manager = make_game_manager();
+    let no_password = other_game_manager.get_password_hash(&GameHandle(HANDLE.to_string()));
+    assert!(check_password(no_password.as_deref(), None));
+}
+
+#[test]
+fn passwords_are_stored_hashed() {
+    let password_hash = hash_password("hunter2");
+    assert!(!password_hash.contains("hunter2"));
+    assert!(password_hash.starts_with("pbkdf2-sha256$"));
+    // Salted, so the same password hashes differently each time.
+    assert_ne!(password_hash, hash_password("hunter2"));
+    assert!(verify_password("hunter2", &password_hash));
+    assert!(!verify_password("hunter3", &password_hash));
+    assert!(!verify_password("hunter2", "not a hash"));
+}
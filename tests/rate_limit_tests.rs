@@ -0,0 +1,148 @@
+use std::env;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant};
+
+use team_heist_tactics::game::GameHandle;
+use team_heist_tactics::rate_limit::{
+    message_type, BucketConfig, ConnectionRateLimiter, PasswordAttemptLimiter, RateLimitConfig,
+    RateLimitDecision, TokenBucket,
+};
+use team_heist_tactics::serializer::{InternalMessage, MAX_FRAME_BYTES};
+use team_heist_tactics::types::main_message::Body;
+
+#[test]
+fn token_bucket_refills_over_time() {
+    let start = Instant::now();
+    let config = BucketConfig {
+        capacity: 3.0,
+        refill_per_sec: 2.0,
+    };
+    let mut bucket = TokenBucket::new(config, start);
+    assert!(bucket.try_take(start));
+    assert!(bucket.try_take(start));
+    assert!(bucket.try_take(start));
+    assert!(!bucket.try_take(start));
+    // Half a second gets one token back.
+    let later = start + Duration::from_millis(500);
+    assert!(bucket.try_take(later));
+    assert!(!bucket.try_take(later));
+    // It never holds more than its capacity.
+    let much_later = later + Duration::from_secs(60);
+    for _ in 0..3 {
+        assert!(bucket.try_take(much_later));
+    }
+    assert!(!bucket.try_take(much_later));
+}
+
+#[test]
+fn limits_apply_per_message_type_then_disconnect() {
+    let start = Instant::now();
+    let config = RateLimitConfig {
+        violations: BucketConfig {
+            capacity: 2.0,
+            refill_per_sec: 0.0,
+        },
+        ..RateLimitConfig::default()
+    };
+    let move_limit = config.per_type["Move"].capacity as usize;
+    let mut limiter = ConnectionRateLimiter::new(config, start);
+
+    for _ in 0..move_limit {
+        assert_eq!(limiter.check("Move", start), RateLimitDecision::Allowed);
+    }
+    assert_eq!(
+        limiter.check("Move", start),
+        RateLimitDecision::Limited("You are sending Move messages too quickly".to_string())
+    );
+    // Other types still have their own allowance.
+    assert_eq!(limiter.check("Chat", start), RateLimitDecision::Allowed);
+    assert!(matches!(
+        limiter.check("Move", start),
+        RateLimitDecision::Limited(_)
+    ));
+    assert_eq!(limiter.check("Move", start), RateLimitDecision::Disconnect);
+}
+
+#[test]
+fn connection_limit_covers_every_message() {
+    let start = Instant::now();
+    let config = RateLimitConfig::default();
+    let capacity = config.connection.capacity as usize;
+    let mut limiter = ConnectionRateLimiter::new(config, start);
+    for _ in 0..capacity {
+        assert_eq!(limiter.check("Chat", start), RateLimitDecision::Allowed);
+    }
+    assert_eq!(
+        limiter.check("Chat", start),
+        RateLimitDecision::Limited("You are sending messages too quickly".to_string())
+    );
+    assert_eq!(
+        limiter.check("Chat", start + Duration::from_secs(1)),
+        RateLimitDecision::Allowed
+    );
+}
+
+#[test]
+fn big_frames_are_rejected() {
+    let frame = vec![0u8; MAX_FRAME_BYTES + 1];
+    let e = InternalMessage::from_bytes(&frame).unwrap_err();
+    assert!(e.to_string().contains("the most allowed is"), "{}", e);
+    let text = " ".repeat(MAX_FRAME_BYTES + 1);
+    assert!(InternalMessage::from_json(&text).is_err());
+    assert_eq!(message_type(&Body::Chat("hello".to_string())), "Chat");
+}
+
+#[test]
+fn every_limit_can_be_set_from_env() {
+    env::set_var("THT_RATE_LIMIT_PLACETILE_BURST", "7");
+    env::set_var("THT_RATE_LIMIT_VIOLATIONS_PER_SEC", "0.5");
+    let config = RateLimitConfig::from_env();
+    let default = RateLimitConfig::default();
+    assert_eq!(config.per_type["PlaceTile"].capacity, 7.0);
+    assert_eq!(
+        config.per_type["PlaceTile"].refill_per_sec,
+        default.per_type["PlaceTile"].refill_per_sec
+    );
+    assert_eq!(config.per_type["Move"], default.per_type["Move"]);
+    assert_eq!(config.violations.refill_per_sec, 0.5);
+    assert_eq!(config.connection, default.connection);
+}
+
+#[test]
+fn only_wrong_passwords_use_up_attempts() {
+    let start = Instant::now();
+    let limiter = PasswordAttemptLimiter::new(BucketConfig {
+        capacity: 2.0,
+        refill_per_sec: 0.1,
+    });
+    let game = GameHandle("apple".to_string());
+    let alice = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let bob = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+    // Right passwords get their attempt back.
+    for _ in 0..5 {
+        assert!(limiter.try_attempt(&game, alice, start));
+        limiter.succeeded(&game, alice);
+    }
+    assert!(limiter.try_attempt(&game, alice, start));
+    assert!(limiter.try_attempt(&game, alice, start));
+    assert!(!limiter.try_attempt(&game, alice, start));
+    // Other addresses aren't affected.
+    assert!(limiter.try_attempt(&game, bob, start));
+    assert!(limiter.try_attempt(&game, alice, start + Duration::from_secs(10)));
+}
+
+#[test]
+fn wrong_passwords_for_one_game_dont_lock_out_others() {
+    let start = Instant::now();
+    let limiter = PasswordAttemptLimiter::new(BucketConfig {
+        capacity: 1.0,
+        refill_per_sec: 0.1,
+    });
+    // Everyone behind the same proxy has the same address.
+    let proxy = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    let apple = GameHandle("apple".to_string());
+    let banana = GameHandle("banana".to_string());
+    assert!(limiter.try_attempt(&apple, proxy, start));
+    assert!(!limiter.try_attempt(&apple, proxy, start));
+    assert!(limiter.try_attempt(&banana, proxy, start));
+}
@@ -0,0 +1,144 @@
+use team_heist_tactics::game::MoveValidity;
+use team_heist_tactics::scenario::{assert_board, build_game, run_command, DEFAULT_PLAYER_NAME};
+use team_heist_tactics::types::{HeisterColor, MapPosition, SquareType};
+
+#[test]
+fn scenario_sets_up_heisters_and_timers() {
+    let game = build_game(
+        r#"
+        tile 1a at 0 0
+        board:
+        ._.._..O.._.
+        |*        @|
+        ._.. .. .._.
+        ._.. .. .._.
+        P   P  O  @|
+        ._.. .. .._.
+        ._.. .. .._.
+        |@     G||ZG
+        ._.. .. .._.
+        ._.. .. .._.
+        |@     Z||&|
+        ._..Y.._.._.
+        Escaped: Y
+        "#,
+    )
+    .unwrap();
+
+    let green = game
+        .game_state
+        .get_heister_from_vec(HeisterColor::Green)
+        .unwrap();
+    assert_eq!(green.map_position, MapPosition { x: 2, y: 2 });
+    let yellow = game
+        .game_state
+        .get_heister_from_vec(HeisterColor::Yellow)
+        .unwrap();
+    assert!(yellow.has_escaped);
+    let grid = game.game_state.get_absolute_grid();
+    assert_eq!(
+        grid[&MapPosition { x: 0, y: 0 }].square_type,
+        SquareType::TimerFlipUsed
+    );
+
+    // A typo in the board is caught.
+    let typo = build_game(
+        r#"
+        tile 1a at 0 0
+        board:
+        ._.._..O.._.
+        |x        @|
+        ._.. .. .._.
+        ._.. .. .._.
+        P   P  O  @|
+        ._.. .. .._.
+        ._.. .. .._.
+        |@  Y  G| ZG
+        ._.. .. .._.
+        ._.. .. .._.
+        |@     Z||&|
+        ._..Y.._.._.
+        "#,
+    );
+    assert!(typo.is_err());
+}
+
+#[test]
+fn heisters_block_each_other() {
+    let mut game = build_game(
+        r#"
+        tile 1a at 0 0
+        board:
+        ._.._..O.._.
+        |x        @|
+        ._.. .. .._.
+        ._.. .. .._.
+        PY     G  @|
+        ._.. .. .._.
+        ._.. .. .._.
+        |@      ||ZG
+        ._.. .. .._.
+        ._.. .. .._.
+        |P  O  Z||&|
+        ._..Y.._.._.
+        "#,
+    )
+    .unwrap();
+
+    let validity = run_command(&mut game, DEFAULT_PLAYER_NAME, "yellow east 2");
+    assert!(validity.is_invalid());
+    let validity = run_command(&mut game, DEFAULT_PLAYER_NAME, "yellow east");
+    assert_eq!(validity, MoveValidity::Valid);
+    assert_board(
+        &game,
+        r#"
+        ._.._..O.._.
+        |x        @|
+        ._.. .. .._.
+        ._.. .. .._.
+        P   Y  G  @|
+        ._.. .. .._.
+        ._.. .. .._.
+        |@      ||ZG
+        ._.. .. .._.
+        ._.. .. .._.
+        |P  O  Z||&|
+        ._..Y.._.._.
+        "#,
+    );
+}
+
+#[test]
+fn players_only_use_their_abilities() {
+    let mut game = build_game(
+        r#"
+        tile 1a at 0 0
+        player alice: MoveEast
+        player bob: MoveWest RevealTiles
+        board:
+        ._.._..O.._.
+        |x        @|
+        ._.. .. .._.
+        ._.. .. .._.
+        P   P  O  @|
+        ._.. .. .._.
+        ._.. .. .._.
+        |@  Y  G||ZG
+        ._.. .. .._.
+        ._.. .. .._.
+        |@     Z||&|
+        ._..Y.._.._.
+        "#,
+    )
+    .unwrap();
+
+    assert!(run_command(&mut game, "alice", "yellow west").is_invalid());
+    assert_eq!(
+        run_command(&mut game, "bob", "yellow west"),
+        MoveValidity::Valid
+    );
+    assert_eq!(
+        run_command(&mut game, "alice", "yellow east"),
+        MoveValidity::Valid
+    );
+}
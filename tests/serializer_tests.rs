@@ -0,0 +1,76 @@
+use team_heist_tactics::serializer::{
+    check_protocol_version, InternalMessage, WireFormat, PROTOCOL_VERSION,
+};
+use team_heist_tactics::types::main_message::Body;
+use team_heist_tactics::types::{
+    HeisterColor, Hello, Internal, MainMessage, MapPosition, Move, ServerHello,
+};
+
+#[test]
+fn wire_format_follows_subprotocol() {
+    assert_eq!(WireFormat::from_protocols_header(None), WireFormat::Proto);
+    assert_eq!(
+        WireFormat::from_protocols_header(Some("tht.json")),
+        WireFormat::Json
+    );
+    assert_eq!(
+        WireFormat::from_protocols_header(Some("chat, tht.json, tht.proto")),
+        WireFormat::Json
+    );
+    assert_eq!(
+        WireFormat::from_protocols_header(Some("tht.proto,tht.json")),
+        WireFormat::Proto
+    );
+    assert_eq!(
+        WireFormat::from_protocols_header(Some("chat")),
+        WireFormat::Proto
+    );
+    assert_eq!(WireFormat::Json.subprotocol(), "tht.json");
+}
+
+#[test]
+fn messages_round_trip_through_json() {
+    let m = Move {
+        heister_color: HeisterColor::Green,
+        position: MapPosition { x: 2, y: -1 },
+    };
+    let internal_message = InternalMessage {
+        main_message: MainMessage {
+            body: Some(Body::Move(m.to_proto())),
+        },
+    };
+    let json = internal_message.to_json();
+    assert!(json.contains("\"Move\""), "{}", json);
+
+    let decoded = InternalMessage::from_json(&json).unwrap();
+    assert_eq!(decoded.main_message, internal_message.main_message);
+    assert_eq!(decoded.to_bytes(), internal_message.to_bytes());
+
+    assert!(InternalMessage::from_json("{\"body\": {\"Nope\": {}}}").is_err());
+}
+
+#[test]
+fn only_known_protocol_versions_are_accepted() {
+    assert!(check_protocol_version(PROTOCOL_VERSION).is_ok());
+    assert!(check_protocol_version(0).is_err());
+    let e = check_protocol_version(PROTOCOL_VERSION + 1).unwrap_err();
+    assert!(e.to_string().contains("try reloading the page"), "{}", e);
+
+    let hello = InternalMessage::from_json(
+        "{\"body\": {\"Hello\": {\"protocol_version\": 1, \"client_name\": \"devtools\"}}}",
+    )
+    .unwrap();
+    match hello.main_message.body {
+        Some(Body::Hello(h)) => assert_eq!(Hello::from_proto(h).client_name, "devtools"),
+        other => panic!("Expected a Hello, got {:?}", other),
+    }
+
+    match InternalMessage::from_server_hello().main_message.body {
+        Some(Body::ServerHello(sh)) => {
+            let server_hello = ServerHello::from_proto(sh);
+            assert_eq!(server_hello.protocol_version, PROTOCOL_VERSION);
+            assert!(server_hello.capabilities.contains(&"json".to_string()));
+        }
+        other => panic!("Expected a ServerHello, got {:?}", other),
+    }
+}
@@ -0,0 +1,60 @@
+use team_heist_tactics::game::{Game, GameHandle, GameOptions};
+use team_heist_tactics::load_map::tile_2;
+use team_heist_tactics::solver::{new_seeded_game, solve, Solvability};
+use team_heist_tactics::types::{HeisterColor, MapPosition, ESCAPED};
+
+/// Sets up a game where every item has been taken and everyone but purple has
+/// escaped. Tile 2, which has the purple escape, is placed off to the side.
+fn setup_escape_game(purple_position: MapPosition) -> Game {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let game_options = GameOptions {
+        shuffle_tiles: false,
+        ..GameOptions::default()
+    };
+    let mut game = Game::new(GameHandle("solver escape".to_string()), game_options);
+    let mut tile = tile_2();
+    tile.position = MapPosition { x: 8, y: 0 };
+    game.game_state.tiles.push(tile);
+    game.tile_deck.clear();
+    game.game_state.all_items_taken = true;
+    for heister in game.game_state.heisters.iter_mut() {
+        heister.has_taken_item = true;
+        match heister.heister_color {
+            HeisterColor::Purple => heister.map_position = purple_position,
+            _ => {
+                heister.map_position = *ESCAPED;
+                heister.has_escaped = true;
+            }
+        }
+    }
+    game
+}
+
+#[test]
+fn solver_finds_fewest_moves() {
+    // Escalator up to the square next to the escape, then step east.
+    let game = setup_escape_game(MapPosition { x: 9, y: 1 });
+    let report = solve(&game, 1000);
+    assert_eq!(report.solvability, Solvability::Solvable { min_moves: 2 });
+}
+
+#[test]
+fn solver_reports_unsolvable() {
+    // Purple is stuck on tile 1a with no way to reach tile 2.
+    let game = setup_escape_game(MapPosition { x: 1, y: 1 });
+    let report = solve(&game, 1000);
+    assert_eq!(report.solvability, Solvability::Unsolvable);
+}
+
+#[test]
+fn seeded_games_are_repeatable() {
+    let names =
+        |game: &Game| -> Vec<String> { game.tile_deck.iter().map(|t| t.name.clone()).collect() };
+    assert_eq!(names(&new_seeded_game(7)), names(&new_seeded_game(7)));
+
+    // A full game is too big to search with a tiny limit, which isn't the
+    // same as it being unwinnable.
+    let report = solve(&new_seeded_game(7), 50);
+    assert_eq!(report.solvability, Solvability::Unknown);
+    assert!(report.timer_squares > 0);
+}
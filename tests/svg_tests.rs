@@ -0,0 +1,42 @@
+use team_heist_tactics::scenario::build_game;
+use team_heist_tactics::svg::render_svg;
+
+#[test]
+fn svg_shows_board_and_heisters() {
+    let game = build_game(
+        r#"
+        tile 1a at 0 0
+        board:
+        ._.._..O.._.
+        |*        @|
+        ._.. .. .._.
+        ._.. .. .._.
+        P   P  O  @|
+        ._.. .. .._.
+        ._.. .. .._.
+        |@     G||ZG
+        ._.. .. .._.
+        ._.. .. .._.
+        |@     Z||&|
+        ._..Y.._.._.
+        Escaped: Y
+        "#,
+    )
+    .unwrap();
+    let svg = render_svg(&game.game_state);
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"180\""));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    // One per square of the tile. The background has no x, so isn't counted.
+    assert_eq!(svg.matches("<rect x=").count(), 16);
+    // Four heisters, with yellow drawn under the board since it escaped.
+    for letter in &["Y", "P", "G", "O"] {
+        assert!(svg.contains(&format!(
+            "font-weight=\"bold\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>",
+            letter
+        )));
+    }
+    assert!(svg.contains("<circle cx=\"30\" cy=\"200\""));
+    // The used timer is greyed out.
+    assert!(svg.contains("fill=\"#d8d4cc\">T</text>"));
+}
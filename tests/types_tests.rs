@@ -1,7 +1,10 @@
 use serde_json;
-use team_heist_tactics::load_map::tile_1a;
+use team_heist_tactics::load_map::{
+    load_tile_json_from_path, parse_tile_art, tile_1a, tile_2, tile_3, tile_4, tile_5, tile_6,
+    tile_7, tile_8, tile_9, tile_art, tile_art_to_json,
+};
 #[allow(dead_code, unused_imports)]
-use team_heist_tactics::types::{MapPosition, Square, Tile};
+use team_heist_tactics::types::{MapPosition, SerializableTile, Square, Tile};
 
 #[test]
 fn load_map_position() {
@@ -25,3 +28,38 @@ fn tile_rotation_identity() {
     let u = Tile::from_matrix(m2, t.name.clone(), t.position.clone(), 0);
     assert_eq!(t, u);
 }
+
+#[test]
+fn tile_art_round_trip() {
+    let tiles = vec![
+        tile_1a(),
+        tile_2(),
+        tile_3(),
+        tile_4(),
+        tile_5(),
+        tile_6(),
+        tile_7(),
+        tile_8(),
+        tile_9(),
+    ];
+    for tile in tiles {
+        let art = tile_art(&tile);
+        let parsed = parse_tile_art(&art, &tile.name).expect("Failed to parse tile art");
+        assert_eq!(parsed.pp(), tile.pp(), "\n{}", art);
+        assert_eq!(parsed, tile, "\n{}", art);
+
+        // The JSON should match what's already in data/tiles.
+        let json = tile_art_to_json(&art, &tile.name).unwrap();
+        let st: SerializableTile = serde_json::from_str(&json).unwrap();
+        let path = format!("data/tiles/{}.json", tile.name);
+        assert_eq!(
+            Tile::from(st),
+            load_tile_json_from_path(path.clone()),
+            "{}",
+            path
+        );
+    }
+
+    // The colors line is needed for teleport pads, items and escapes.
+    assert!(parse_tile_art(&tile_1a().pp(), "1a").is_err());
+}
@@ -1,6 +1,9 @@
 export const WEBSOCKET_ACTION_PREFIX = "REDUX_WEBSOCKET";
 export const WEBSOCKET_ACTION_PREFIX_FULL = "REDUX_WEBSOCKET::";
 
+// Must match PROTOCOL_VERSION in serializer.rs, bump both when types.proto changes.
+export const PROTOCOL_VERSION = 1;
+
 export const SERVER_WIDTH = 500;
 export const SERVER_HEIGHT = 500;
 
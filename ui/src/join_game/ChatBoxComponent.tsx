@@ -1,7 +1,6 @@
 import React, { useEffect, useRef, useState } from "react";
 import {
   chatBoxActiveSelector,
-  playerNameSelector,
   playersMaySpeakSelector,
   setChatBoxFocus,
 } from "./slice";
@@ -25,7 +24,6 @@ export const useFocus = () => {
 
 const ChatBoxComponent = () => {
   const dispatch = useDispatch();
-  const player_name = useSelector(playerNameSelector);
   const players_may_speak = useSelector(playersMaySpeakSelector);
   const chat_box_active = useSelector(chatBoxActiveSelector);
   const [box_content, set_box_content] = useState("");
@@ -71,8 +69,7 @@ const ChatBoxComponent = () => {
     if (box_content.length == 0) {
       return;
     }
-    let chat = `${player_name}: ${box_content}`;
-    dispatch(sendChat(chat));
+    dispatch(sendChat(box_content));
     set_box_content("");
   };
 
@@ -1,5 +1,6 @@
 import {
   ActiveHeisterKeyboardComponent,
+  HintButtonComponent,
   MaySpeakComponent,
   PlayerAbilities,
   ResetMapComponent,
@@ -24,6 +25,7 @@ import React, { useState } from "react";
 import {
   canvasPositionToMapPosition,
   mapPositionToCanvasPosition,
+  presenceSuffix,
   useWindowDimensions,
 } from "./helpers";
 import {
@@ -475,18 +477,14 @@ const GameWindowComponent = () => {
     var self: JSX.Element | null = null;
     for (let i = 0; i < proto_players.length; i++) {
       var proto_player = proto_players[i];
-      var name_prefix: string;
       var proto_name = proto_player.getName();
-      if (proto_name === player_name) {
-        name_prefix = "Your";
-      } else {
-        name_prefix = `${proto_name}'s`;
-      }
       var as = (
         <Provider key={i} store={store}>
           <PlayerAbilities
             key={i + 300}
-            name_prefix={name_prefix}
+            name={proto_name}
+            presence_suffix={presenceSuffix(proto_player)}
+            is_self={proto_name === player_name}
             proto_abilities={proto_player.getAbilitiesList()}
           />
         </Provider>
@@ -647,6 +645,7 @@ const GameWindowComponent = () => {
       </div>
       <div style={styles.resetGameWindowOverlay}>
         <ResetMapComponent reset_parent_func={resetMap} />
+        <HintButtonComponent />
       </div>
       <div style={styles.invalidMessagesOverlay}>
         <MessagesComponent />
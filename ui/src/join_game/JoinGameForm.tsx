@@ -7,6 +7,7 @@ import { useDispatch } from "react-redux";
 const defaults: StagingJoinGameThing = {
   name: "",
   handle: "",
+  password: "",
 };
 
 type JoinGameFormProps = {
@@ -39,10 +40,14 @@ const JoinGameForm = ({ existing }: JoinGameFormProps) => {
     getInitial("handle", defaults, existing)
   );
 
+  // Not read from the URL, so it doesn't end up in links people share.
+  const [password, setPassword] = useState(defaults.password);
+
   const stateToStagingJoinGameThing = (): StagingJoinGameThing => {
     return {
       name,
       handle,
+      password,
     };
   };
 
@@ -99,6 +104,16 @@ const JoinGameForm = ({ existing }: JoinGameFormProps) => {
           />
         </label>
         <br />
+        <label>
+          <input
+            type="password"
+            placeholder="Game password (if it has one)"
+            value={password}
+            onChange={(e) => setPassword(e.target.value)}
+            autoComplete="off"
+          />
+        </label>
+        <br />
         <button type="submit" onClick={onSubmit}>
           Join Game
         </button>
@@ -3,6 +3,7 @@ import { useDispatch, useSelector } from "react-redux";
 
 import { Player } from "../generated/types_pb";
 import React from "react";
+import { presenceSuffix } from "./helpers";
 import { startGame } from "./api";
 
 const LobbyForm = () => {
@@ -17,7 +18,12 @@ const LobbyForm = () => {
 
   const players = game_state!
     .getPlayersList()
-    .map((p: Player) => <li key={p.getName()}>{p.getName()}</li>);
+    .map((p: Player) => (
+      <li key={p.getName()}>
+        {p.getName()}
+        {presenceSuffix(p)}
+      </li>
+    ));
 
   return (
     <div>
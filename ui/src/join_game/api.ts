@@ -1,18 +1,22 @@
 import { ConnectionStatus, StagingJoinGameThing } from "./types";
 import {
+  Command,
   GameState,
   Heister,
   HeisterColor,
   MainMessage,
   MapPosition,
   Move,
+  Nudge,
   PlaceTile,
+  RequestHint,
   StartGame,
 } from "../generated/types_pb";
 import { connect, send } from "@giantmachines/redux-websocket";
 import { registerPlayerNameGameHandle, selectKeyboardHeister } from "./slice";
 
 import { MoveDirection } from "./types";
+import { PROTOCOL_VERSION } from "../constants/other";
 
 export function joinGame(join_game_thing: StagingJoinGameThing) {
   return async (dispatch) => {
@@ -34,6 +38,12 @@ export function joinGame(join_game_thing: StagingJoinGameThing) {
     var serverUrl = new URL(urlString);
     serverUrl.searchParams.set("name", join_game_thing.name);
     serverUrl.searchParams.set("handle", join_game_thing.handle);
+    serverUrl.searchParams.set("protocol_version", PROTOCOL_VERSION.toString());
+    // We can show the game to people who turn up after it's full or started.
+    serverUrl.searchParams.set("spectate", "true");
+    if (join_game_thing.password.length > 0) {
+      serverUrl.searchParams.set("password", join_game_thing.password);
+    }
     dispatch(
       registerPlayerNameGameHandle({
         player_name: join_game_thing.name,
@@ -225,6 +235,35 @@ export function sendChat(chat: string) {
   };
 }
 
+export function sendNudge(target_player: string) {
+  return async (dispatch) => {
+    var nudge = new Nudge();
+    nudge.setTargetPlayer(target_player);
+    var main_message = new MainMessage();
+    main_message.setNudge(nudge);
+    dispatch(send(main_message));
+  };
+}
+
+export function sendRequestHint() {
+  return async (dispatch) => {
+    var main_message = new MainMessage();
+    main_message.setRequestHint(new RequestHint());
+    dispatch(send(main_message));
+  };
+}
+
+// Send a move written out as text, e.g. "green east 3" or "reveal yellow".
+export function sendCommand(text: string) {
+  return async (dispatch) => {
+    var command = new Command();
+    command.setText(text);
+    var main_message = new MainMessage();
+    main_message.setCommand(command);
+    dispatch(send(main_message));
+  };
+}
+
 // Take a key input, convert to an enum representing different things
 // the user wants to do, then match on that instead.
 export function handleKeyInput(
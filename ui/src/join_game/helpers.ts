@@ -2,7 +2,7 @@ import { INTERNAL_SQUARE_SIZE, INTERNAL_TILE_OFFSET } from "../constants/other";
 import { useEffect, useState } from "react";
 
 import { CanvasPosition } from "./types";
-import { MapPosition } from "../generated/types_pb";
+import { MapPosition, Player, Presence } from "../generated/types_pb";
 
 /**
  * Converts a tile position (R moves right, D moves down) to a grid position.
@@ -157,6 +157,18 @@ function getWindowDimensions() {
   };
 }
 
+// Shown after a player's name, so people know who is actually there.
+export const presenceSuffix = (player: Player): string => {
+  switch (player.getPresence()) {
+    case Presence.AWAY:
+      return " (away)";
+    case Presence.OFFLINE:
+      return " (offline)";
+    default:
+      return "";
+  }
+};
+
 export function useWindowDimensions() {
   const [windowDimensions, setWindowDimensions] = useState(
     getWindowDimensions()
@@ -1,8 +1,7 @@
-import { getColor, sendChat } from "./api";
+import { getColor, sendNudge, sendRequestHint } from "./api";
 import {
   heisterSelectedSelector,
   playerIsSpectatorSelector,
-  playerNameSelector,
   playersMaySpeakSelector,
 } from "./slice";
 import { useDispatch, useSelector } from "react-redux";
@@ -58,22 +57,32 @@ export const ActiveHeisterKeyboardComponent = ({
   );
 };
 
+export const HintButtonComponent = () => {
+  const dispatch = useDispatch();
+  return (
+    <button
+      style={{ width: 110, height: 40 }}
+      onClick={() => dispatch(sendRequestHint())}
+    >
+      Hint
+    </button>
+  );
+};
+
 type TapButtonComponentProps = {
-  name_prefix: string;
+  name: string;
+  is_self: boolean;
 };
 export const TapButtonComponent = ({
-  name_prefix,
+  name,
+  is_self,
 }: TapButtonComponentProps) => {
   const dispatch = useDispatch();
-  const player_name = useSelector(playerNameSelector);
 
   const onClick = (_event) => {
-    let recipient = name_prefix.split("'").slice(0, -1).join("'");
-    let msg = `${player_name}: tap ${recipient}`;
-    dispatch(sendChat(msg)); // todo
+    dispatch(sendNudge(name));
   };
 
-  const is_self = name_prefix === "Your";
   const pointer_events = is_self ? "none" : "auto";
   const text = is_self ? "-" : "Tap";
   const class_name = is_self ? "invisible" : undefined;
@@ -95,11 +104,15 @@ export const TapButtonComponent = ({
 };
 
 type PlayerAbilitiesProps = {
-  name_prefix: string;
+  name: string;
+  presence_suffix: string;
+  is_self: boolean;
   proto_abilities: number[];
 };
 export const PlayerAbilities = ({
-  name_prefix,
+  name,
+  presence_suffix,
+  is_self,
   proto_abilities,
 }: PlayerAbilitiesProps) => {
   const player_is_spectator = useSelector(playerIsSpectatorSelector);
@@ -134,10 +147,12 @@ export const PlayerAbilities = ({
     );
   }
 
+  const name_prefix = is_self ? "Your" : `${name}${presence_suffix}'s`;
+
   return (
     <p>
       {player_is_spectator ? null : (
-        <TapButtonComponent name_prefix={name_prefix} />
+        <TapButtonComponent name={name} is_self={is_self} />
       )}
       &nbsp;&nbsp;
       {name_prefix} abilities: {abilities_string}
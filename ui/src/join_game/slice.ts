@@ -183,20 +183,27 @@ const joinGameSlice = createSlice({
         state.num_invalid_move_attempts += 1;
         pushToPlayerMessageQueue(state.player_message_queue, msg);
       }
-      if (main_message.hasChat()) {
-        var msg = main_message.getChat()!;
-        if (msg.includes("tap ")) {
-          let sender = msg.split(": ")[0];
-          if (sender === state.player_name) {
-            sender = "You";
-          }
-          var recipient = msg.split("tap ").slice(-1)[0];
-          if (recipient === state.player_name) {
-            recipient = "you";
-          }
-          msg = `${sender} tapped at ${recipient}!`;
-          window["tap_audio_object"].play();
+      if (main_message.hasChatMessage()) {
+        let chat_message = main_message.getChatMessage()!;
+        let msg = `${chat_message.getSender()}: ${chat_message.getText()}`;
+        pushToPlayerMessageQueue(state.player_message_queue, msg);
+      }
+      if (main_message.hasNudge()) {
+        let nudge = main_message.getNudge()!;
+        let sender = nudge.getSender();
+        if (sender === state.player_name) {
+          sender = "You";
+        }
+        let recipient = nudge.getTargetPlayer();
+        if (recipient === state.player_name) {
+          recipient = "you";
         }
+        let msg = `${sender} tapped at ${recipient}!`;
+        window["tap_audio_object"].play();
+        pushToPlayerMessageQueue(state.player_message_queue, msg);
+      }
+      if (main_message.hasHint()) {
+        let msg = `Hint: ${main_message.getHint()!.getText()}`;
         pushToPlayerMessageQueue(state.player_message_queue, msg);
       }
       state.game_state = game_state;
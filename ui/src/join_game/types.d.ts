@@ -6,6 +6,8 @@ export interface JoinGameThing {
 export interface StagingJoinGameThing {
   name: string;
   handle: string;
+  // Empty if the game doesn't have one.
+  password: string;
 }
 
 export enum ConnectionStatus {